@@ -1,33 +1,191 @@
 use clap::Parser;
+use std::io::{self, IsTerminal};
+
+use crate::display::{DataType, Encoding, Endian, Highlight, Language, Palette, Radix, Style};
+use crate::io::ByteRange;
+use crate::utils::color::ColorChoice;
+use crate::utils::parsing::parse_offset;
+
+/// Bytes dumped when neither `--bytes` nor `--all` is given
+pub const DEFAULT_BYTES: usize = 256;
+
+/// Bytes per line without `--width`; `--style xxd` uses xxd's 16
+pub const DEFAULT_WIDTH: usize = 64;
+
+/// Output format (`--format`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Human-readable rows and `Label: value` metadata
+    Text,
+    /// One JSON record per line; see "JSON output" in the Readme
+    Json,
+    /// A standalone HTML page with themed, hoverable rows
+    Html,
+}
+
+impl OutputFormat {
+    /// Parse `text`, `json` or `html`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "text" => Some(OutputFormat::Text),
+            "json" | "ndjson" => Some(OutputFormat::Json),
+            "html" => Some(OutputFormat::Html),
+            _ => None,
+        }
+    }
+}
 
 /// Command-line arguments for hhead
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 pub struct Args {
-    /// Width of hex display (bytes per line)
-    #[arg(long, default_value_t = 64)]
-    pub width: usize,
-
-    /// Number of bytes to read
-    #[arg(long, default_value_t = 256)]
-    pub bytes: usize,
+    /// Width of hex display (bytes per line) [default: 64; 16 with --style xxd, 12 with --export]
+    #[arg(long)]
+    pub width: Option<usize>,
 
-    /// Input file
+    /// Number of bytes to read [default: 256]
     #[arg(long)]
-    pub input: String,
+    pub bytes: Option<usize>,
 
-    /// Colorize output
+    /// Dump everything from the start offset to the end of the input (overrides --bytes)
     #[arg(long, default_value_t = false)]
-    pub color: bool,
+    pub all: bool,
+
+    /// Keep dumping bytes as the file grows, like `tail -f` (implies --all)
+    #[arg(long, default_value_t = false, conflicts_with = "bytes")]
+    pub follow: bool,
+
+    /// Decompress gzip/zstd/xz/bzip2 input and dump the payload instead
+    #[arg(long, default_value_t = false, conflicts_with_all = ["follow", "mmap"])]
+    pub decompress: bool,
+
+    /// Memory-map regular files instead of reading them in chunks
+    #[arg(long, default_value_t = false, conflicts_with = "follow")]
+    pub mmap: bool,
+
+    /// Start offset: decimal or 0x-prefixed hex; negative counts back from the end
+    #[arg(
+        long,
+        visible_alias = "offset",
+        default_value = "0",
+        allow_hyphen_values = true,
+        value_parser = parse_skip
+    )]
+    pub skip: i64,
+
+    /// Dump only START..END or START+LEN (decimal or 0x hex, negative from the end); repeatable,
+    /// with overlapping regions merged
+    #[arg(
+        long,
+        value_name = "RANGE",
+        allow_hyphen_values = true,
+        value_parser = ByteRange::from_spec,
+        conflicts_with_all = ["skip", "bytes", "all", "tail", "follow", "reverse", "export"]
+    )]
+    pub range: Vec<ByteRange>,
+
+    /// Dump the last N bytes of the input (replaces --skip, --bytes and --all)
+    #[arg(long, value_name = "N", conflicts_with_all = ["skip", "bytes", "all"])]
+    pub tail: Option<u64>,
+
+    /// Input file(s); "-" reads standard input, which is also the default
+    #[arg(long, num_args = 1.., value_name = "FILE")]
+    pub input: Vec<String>,
+
+    /// Input files given positionally (e.g. expanded from a shell glob)
+    #[arg(value_name = "FILES")]
+    pub files: Vec<String>,
+
+    /// When to colorize output: auto, always or never; a bare --color means always.
+    /// auto colors a terminal and honors NO_COLOR, CLICOLOR and CLICOLOR_FORCE
+    #[arg(
+        long,
+        value_name = "WHEN",
+        default_value = "auto",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "always",
+        value_parser = parse_color_choice
+    )]
+    pub color: ColorChoice,
+
+    /// Color theme for --color: dark, light, high-contrast, colorblind, or a TOML theme file
+    /// [default: $XDG_CONFIG_HOME/hhead/theme.toml if present, else dark]
+    #[arg(long, value_name = "NAME|FILE")]
+    pub theme: Option<String>,
+
+    /// Byte-class colors for --color, applied on top of the theme, e.g. "null=blue,ff=#ff8800"
+    /// (classes: null, printable, whitespace, control, nonascii, ff)
+    #[arg(long, value_name = "CLASS=COLOR,...", value_parser = parse_palette)]
+    pub palette: Option<String>,
 
     /// Print file metadata
     #[arg(long, default_value_t = false)]
     pub meta: bool,
 
-    /// Try to decode and align in UTF-8 text mode
-    #[arg(long, default_value_t = false)]
+    /// Try to decode and align in UTF-8 text mode (same as --encoding utf8)
+    #[arg(long, default_value_t = false, conflicts_with = "encoding")]
     pub utf8: bool,
 
+    /// Character column encoding: ascii, utf8, latin1, cp437, ebcdic (CP037), utf16le or utf16be
+    #[arg(long, default_value = "ascii", value_parser = parse_encoding)]
+    pub encoding: Encoding,
+
+    /// Base for the byte cells: hex, oct, dec or bin
+    #[arg(long, default_value = "hex", value_parser = parse_radix)]
+    pub radix: Radix,
+
+    /// Bytes per cell: 1, 2, 4 or 8 (like `xxd -e` / `od -t x4`)
+    #[arg(long, default_value_t = 1, value_name = "N", value_parser = parse_word_size)]
+    pub word_size: usize,
+
+    /// Byte order of multi-byte cells: le or be
+    #[arg(long, default_value = "le", value_parser = parse_endian)]
+    pub endian: Endian,
+
+    /// Decode each row as TYPE in an extra column: i8..i64, u8..u64, f16/f32/f64, time32/time64
+    #[arg(long = "as", value_name = "TYPE", value_parser = parse_data_type)]
+    pub as_type: Option<DataType>,
+
+    /// Show only the --as column, without the byte cells
+    #[arg(long, default_value_t = false, requires = "as_type")]
+    pub as_only: bool,
+
+    /// Show every row instead of collapsing repeated rows into `*`
+    #[arg(short = 'v', long = "no-squeeze", default_value_t = false)]
+    pub no_squeeze: bool,
+
+    /// Highlight LEN bytes from offset START, on COLOR or the theme's highlight color (repeatable)
+    #[arg(long, value_name = "START:LEN[:COLOR]", value_parser = Highlight::from_spec)]
+    pub highlight: Vec<Highlight>,
+
+    /// Row layout: hhead, or xxd for output identical to plain `xxd -c WIDTH`
+    #[arg(long, default_value = "hhead", value_parser = parse_style)]
+    pub style: Style,
+
+    /// Output format: text; json for NDJSON records (one per row, plus --meta and input records);
+    /// or html for a standalone page using the --theme colors
+    #[arg(long, default_value = "text", value_parser = parse_format)]
+    pub format: OutputFormat,
+
+    /// Read a dump in the given --style, --radix, --word-size, --endian and --as and write the original bytes
+    #[arg(
+        short = 'r',
+        long,
+        default_value_t = false,
+        conflicts_with_all = ["follow", "decompress", "mmap", "meta", "minimap", "tail", "as_only"]
+    )]
+    pub reverse: bool,
+
+    /// Write the range as a c, rust, python or go source literal named after the input, instead of a hex dump
+    #[arg(
+        long,
+        value_name = "LANG",
+        value_parser = parse_language,
+        conflicts_with_all = ["follow", "reverse", "meta", "minimap"]
+    )]
+    pub export: Option<Language>,
+
     /// Display image minimap (for BMP, PNG, JPEG)
     #[arg(long, default_value_t = false)]
     pub minimap: bool,
@@ -38,14 +196,168 @@ pub struct Args {
 }
 
 impl Args {
+    /// All inputs in command-line order, or standard input if none were given
+    pub fn inputs(&self) -> Vec<String> {
+        let inputs: Vec<String> = self.input.iter().chain(&self.files).cloned().collect();
+        if inputs.is_empty() { vec!["-".to_string()] } else { inputs }
+    }
+
+    /// Signed start offset, with `--tail N` expressed as `-N`
+    pub fn start_offset(&self) -> i64 {
+        match self.tail {
+            Some(n) => 0i64.saturating_sub_unsigned(n),
+            None => self.skip,
+        }
+    }
+
+    /// Maximum number of bytes to dump, or `None` to read until end of input
+    pub fn byte_limit(&self) -> Option<u64> {
+        if let Some(n) = self.tail {
+            Some(n)
+        } else if self.all || self.follow {
+            None
+        } else {
+            Some(self.bytes.unwrap_or(DEFAULT_BYTES) as u64)
+        }
+    }
+
+    /// Bytes per line, defaulting by `--style`
+    pub fn width(&self) -> usize {
+        match (self.width, self.style) {
+            (Some(width), _) => width,
+            // The defaults of `xxd -i` and `xxd`
+            (None, _) if self.export.is_some() => 12,
+            (None, Style::Xxd) => 16,
+            (None, Style::Hhead | Style::Json | Style::Html) => DEFAULT_WIDTH,
+        }
+    }
+
+    /// Character column encoding, with `--utf8` as a shorthand
+    pub fn encoding(&self) -> Encoding {
+        if self.utf8 { Encoding::Utf8 } else { self.encoding }
+    }
+
+    /// Row layout, with `--format json` taking over from `--style`
+    pub fn style(&self) -> Style {
+        match self.format {
+            OutputFormat::Text => self.style,
+            OutputFormat::Json => Style::Json,
+            OutputFormat::Html => Style::Html,
+        }
+    }
+
+    /// Whether output should be colored with ANSI escapes, given `--color`
+    /// and the environment; JSON and HTML never are
+    pub fn use_color(&self) -> bool {
+        self.format == OutputFormat::Text && self.color.enabled(io::stdout().is_terminal())
+    }
+
     /// Validate command-line arguments
     pub fn validate(&self) -> Result<(), String> {
-        if self.width == 0 {
+        let width = self.width();
+        if width == 0 {
             return Err("width must be positive".to_string());
         }
-        if self.bytes == 0 {
+        if self.bytes == Some(0) {
             return Err("bytes must be positive".to_string());
         }
+        if self.tail == Some(0) {
+            return Err("tail must be positive".to_string());
+        }
+        if !width.is_multiple_of(self.word_size) {
+            return Err(format!("width must be a multiple of the word size ({})", self.word_size));
+        }
+        if let Some(typed) = self.as_type
+            && !width.is_multiple_of(typed.size())
+        {
+            return Err(format!("width must be a multiple of the --as element size ({})", typed.size()));
+        }
+        if !width.is_multiple_of(self.encoding().unit_size()) {
+            return Err("width must be even for UTF-16".to_string());
+        }
+        if self.style == Style::Xxd
+            && (self.radix != Radix::Hex
+                || self.word_size != 1
+                || self.as_type.is_some()
+                || self.encoding() != Encoding::Ascii
+                || !self.highlight.is_empty())
+        {
+            return Err("--style xxd cannot be combined with --radix, --word-size, --as, --encoding or --highlight"
+                .to_string());
+        }
+        if self.format == OutputFormat::Json
+            && (self.style != Style::Hhead
+                || self.reverse
+                || self.export.is_some()
+                || self.minimap
+                || !self.highlight.is_empty())
+        {
+            return Err(
+                "--format json cannot be combined with --style, --reverse, --export, --minimap or --highlight"
+                    .to_string(),
+            );
+        }
+        if self.format == OutputFormat::Html
+            && (self.style != Style::Hhead || self.reverse || self.export.is_some() || self.follow)
+        {
+            return Err(
+                "--format html cannot be combined with --style, --reverse, --export or --follow".to_string()
+            );
+        }
+        if self.follow && self.inputs().len() > 1 {
+            return Err("--follow takes a single input".to_string());
+        }
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+fn parse_color_choice(s: &str) -> Result<ColorChoice, String> {
+    ColorChoice::from_name(s).ok_or_else(|| format!("invalid color choice '{}', expected auto, always or never", s))
+}
+
+fn parse_encoding(s: &str) -> Result<Encoding, String> {
+    Encoding::from_name(s).ok_or_else(|| {
+        format!("invalid encoding '{}', expected ascii, utf8, latin1, cp437, ebcdic, utf16le or utf16be", s)
+    })
+}
+
+fn parse_style(s: &str) -> Result<Style, String> {
+    Style::from_name(s).ok_or_else(|| format!("invalid style '{}', expected hhead or xxd", s))
+}
+
+fn parse_format(s: &str) -> Result<OutputFormat, String> {
+    OutputFormat::from_name(s).ok_or_else(|| format!("invalid format '{}', expected text, json or html", s))
+}
+
+fn parse_language(s: &str) -> Result<Language, String> {
+    Language::from_name(s).ok_or_else(|| format!("invalid language '{}', expected c, rust, python or go", s))
+}
+
+fn parse_radix(s: &str) -> Result<Radix, String> {
+    Radix::from_name(s).ok_or_else(|| format!("invalid radix '{}', expected hex, oct, dec or bin", s))
+}
+
+fn parse_word_size(s: &str) -> Result<usize, String> {
+    match s.parse() {
+        Ok(n @ (1 | 2 | 4 | 8)) => Ok(n),
+        _ => Err(format!("invalid word size '{}', expected 1, 2, 4 or 8", s)),
+    }
+}
+
+fn parse_endian(s: &str) -> Result<Endian, String> {
+    Endian::from_name(s).ok_or_else(|| format!("invalid endianness '{}', expected le or be", s))
+}
+
+fn parse_data_type(s: &str) -> Result<DataType, String> {
+    DataType::from_name(s).ok_or_else(|| {
+        format!("invalid type '{}', expected i8..i64, u8..u64, f16, f32, f64, time32 or time64", s)
+    })
+}
+
+fn parse_palette(s: &str) -> Result<String, String> {
+    Palette::default().with_overrides(s).map(|_| s.to_string())
+}
+
+fn parse_skip(s: &str) -> Result<i64, String> {
+    parse_offset(s).ok_or_else(|| format!("invalid offset '{}', expected e.g. 4096, 0x1000 or -512", s))
+}
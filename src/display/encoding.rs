@@ -0,0 +1,335 @@
+//! Text encodings for the character column
+
+use unicode_width::UnicodeWidthChar;
+
+use super::palette::ByteClass;
+
+/// Stand-in glyph for each byte of a character cut by a row boundary
+pub const CONTINUATION: char = '·';
+
+/// How bytes are decoded for the character column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// Printable ASCII; everything else is `.`
+    Ascii,
+    Utf8,
+    /// ISO-8859-1
+    Latin1,
+    /// The original IBM PC / DOS code page, with its graphic glyphs for control bytes
+    Cp437,
+    /// EBCDIC code page 037 (US/Canada)
+    Ebcdic,
+    Utf16Le,
+    Utf16Be,
+}
+
+/// One decoded character and the terminal cells it covers
+///
+/// `cells` is at least the character's display width; the remainder is
+/// padded so multi-byte code units line up with the bytes they came from.
+/// `bytes` is how many input bytes the glyph was decoded from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Glyph {
+    pub ch: char,
+    pub class: ByteClass,
+    pub cells: usize,
+    pub bytes: usize,
+}
+
+impl Glyph {
+    fn single(ch: char, byte: u8) -> Self {
+        Glyph { ch, class: ByteClass::of(byte), cells: 1, bytes: 1 }
+    }
+
+    /// Display width of `ch` itself, before padding
+    pub fn width(&self) -> usize {
+        self.ch.width().unwrap_or(0)
+    }
+}
+
+impl Encoding {
+    /// Parse an encoding name such as `utf8`, `latin1`, `cp437`, `ebcdic` or `utf16le`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().replace('_', "-").as_str() {
+            "ascii" => Some(Encoding::Ascii),
+            "utf8" | "utf-8" => Some(Encoding::Utf8),
+            "latin1" | "latin-1" | "iso-8859-1" => Some(Encoding::Latin1),
+            "cp437" | "ibm437" | "dos" => Some(Encoding::Cp437),
+            "ebcdic" | "cp037" | "ibm037" => Some(Encoding::Ebcdic),
+            "utf16" | "utf-16" | "utf16le" | "utf-16le" => Some(Encoding::Utf16Le),
+            "utf16be" | "utf-16be" => Some(Encoding::Utf16Be),
+            _ => None,
+        }
+    }
+
+    /// Bytes per code unit; rows must hold a whole number of them
+    pub fn unit_size(self) -> usize {
+        match self {
+            Encoding::Utf16Le | Encoding::Utf16Be => 2,
+            _ => 1,
+        }
+    }
+
+    /// Decode one row for the character column
+    pub fn glyphs(self, chunk: &[u8]) -> Vec<Glyph> {
+        match self {
+            Encoding::Ascii => chunk
+                .iter()
+                .map(|&b| Glyph::single(if (32..=126).contains(&b) { b as char } else { '.' }, b))
+                .collect(),
+            Encoding::Utf8 => utf8_glyphs(chunk),
+            Encoding::Latin1 => chunk
+                .iter()
+                .map(|&b| {
+                    let c = b as char;
+                    // Soft hyphen has no visible glyph of its own
+                    Glyph::single(if c.is_control() || b == 0xad { '.' } else { c }, b)
+                })
+                .collect(),
+            Encoding::Cp437 => chunk
+                .iter()
+                .map(|&b| Glyph::single(if b == 0x00 || b == 0xff { '.' } else { cp437(b) }, b))
+                .collect(),
+            Encoding::Ebcdic => chunk.iter().map(|&b| Glyph::single(ebcdic(b).unwrap_or('.'), b)).collect(),
+            Encoding::Utf16Le => utf16_glyphs(chunk, u16::from_le_bytes),
+            Encoding::Utf16Be => utf16_glyphs(chunk, u16::from_be_bytes),
+        }
+    }
+}
+
+fn char_class(c: char) -> ByteClass {
+    if c.is_ascii() { ByteClass::of(c as u8) } else { ByteClass::NonAscii }
+}
+
+fn printable(c: char) -> char {
+    if c.is_control() { '.' } else { c }
+}
+
+/// Placeholder for undecodable bytes, one cell per byte
+fn continuation(bytes: usize) -> Glyph {
+    Glyph { ch: CONTINUATION, class: ByteClass::NonAscii, cells: bytes, bytes }
+}
+
+/// Decode a row as UTF-8
+///
+/// Control characters become `.` and invalid bytes `U+FFFD`, each covering
+/// its display width. A sequence split across rows is decodable in neither,
+/// so its bytes on both sides of the boundary show as [`CONTINUATION`], one
+/// per byte.
+fn utf8_glyphs(chunk: &[u8]) -> Vec<Glyph> {
+    let mut glyphs = Vec::with_capacity(chunk.len());
+
+    // Tail of a character that started on the previous row
+    let lead = chunk.iter().take(3).take_while(|&&b| b & 0xc0 == 0x80).count();
+    glyphs.extend(std::iter::repeat_n(continuation(1), lead));
+
+    let push_str = |glyphs: &mut Vec<Glyph>, text: &str| {
+        glyphs.extend(text.chars().map(|c| {
+            let ch = printable(c);
+            Glyph { ch, class: char_class(c), cells: ch.width().unwrap_or(0), bytes: c.len_utf8() }
+        }));
+    };
+    let mut rest = &chunk[lead..];
+    loop {
+        match std::str::from_utf8(rest) {
+            Ok(text) => {
+                push_str(&mut glyphs, text);
+                break;
+            }
+            Err(e) => {
+                let (valid, after) = rest.split_at(e.valid_up_to());
+                push_str(&mut glyphs, std::str::from_utf8(valid).unwrap_or_default());
+                match e.error_len() {
+                    Some(len) => {
+                        let ch = char::REPLACEMENT_CHARACTER;
+                        glyphs.push(Glyph { ch, class: ByteClass::NonAscii, cells: 1, bytes: len });
+                        rest = &after[len..];
+                    }
+                    // Start of a character that continues on the next row
+                    None => {
+                        glyphs.extend(std::iter::repeat_n(continuation(1), after.len()));
+                        break;
+                    }
+                }
+            }
+        }
+    }
+    glyphs
+}
+
+/// Decode a row as UTF-16, one glyph spanning the two cells of each code
+/// unit (four for a surrogate pair)
+///
+/// A pair split across rows shows as [`CONTINUATION`] on both sides, as does
+/// an odd trailing byte.
+fn utf16_glyphs(chunk: &[u8], unit: fn([u8; 2]) -> u16) -> Vec<Glyph> {
+    let units: Vec<u16> = chunk.chunks_exact(2).map(|pair| unit([pair[0], pair[1]])).collect();
+    let mut glyphs = Vec::with_capacity(units.len());
+    let mut i = 0;
+    while i < units.len() {
+        let (ch, cells) = match units[i] {
+            high @ 0xd800..=0xdbff => match units.get(i + 1) {
+                Some(&low @ 0xdc00..=0xdfff) => {
+                    let code = 0x10000 + ((u32::from(high) - 0xd800) << 10) + (u32::from(low) - 0xdc00);
+                    i += 1;
+                    (char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER), 4)
+                }
+                // High surrogate whose partner is on the next row
+                None => (CONTINUATION, 2),
+                Some(_) => (char::REPLACEMENT_CHARACTER, 2),
+            },
+            // Low surrogate whose partner was on the previous row
+            0xdc00..=0xdfff if i == 0 => (CONTINUATION, 2),
+            0xdc00..=0xdfff => (char::REPLACEMENT_CHARACTER, 2),
+            code => (char::from_u32(u32::from(code)).unwrap_or(char::REPLACEMENT_CHARACTER), 2),
+        };
+        let class = if ch == CONTINUATION { ByteClass::NonAscii } else { char_class(ch) };
+        // UTF-16 spans exactly one cell per byte
+        glyphs.push(Glyph { ch: printable(ch), class, cells, bytes: cells });
+        i += 1;
+    }
+    if chunk.len() % 2 == 1 {
+        glyphs.push(continuation(1));
+    }
+    glyphs
+}
+
+/// Code page 437, with the graphic glyphs DOS shows for control bytes
+fn cp437(byte: u8) -> char {
+    const LOW: [char; 32] = [
+        ' ', '☺', '☻', '♥', '♦', '♣', '♠', '•', '◘', '○', '◙', '♂', '♀', '♪', '♫', '☼', //
+        '►', '◄', '↕', '‼', '¶', '§', '▬', '↨', '↑', '↓', '→', '←', '∟', '↔', '▲', '▼',
+    ];
+    const HIGH: [char; 128] = [
+        'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å',
+        'É', 'æ', 'Æ', 'ô', 'ö', 'ò', 'û', 'ù', 'ÿ', 'Ö', 'Ü', '¢', '£', '¥', '₧', 'ƒ',
+        'á', 'í', 'ó', 'ú', 'ñ', 'Ñ', 'ª', 'º', '¿', '⌐', '¬', '½', '¼', '¡', '«', '»',
+        '░', '▒', '▓', '│', '┤', '╡', '╢', '╖', '╕', '╣', '║', '╗', '╝', '╜', '╛', '┐',
+        '└', '┴', '┬', '├', '─', '┼', '╞', '╟', '╚', '╔', '╩', '╦', '╠', '═', '╬', '╧',
+        '╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫', '╪', '┘', '┌', '█', '▄', '▌', '▐', '▀',
+        'α', 'ß', 'Γ', 'π', 'Σ', 'σ', 'µ', 'τ', 'Φ', 'Θ', 'Ω', 'δ', '∞', 'φ', 'ε', '∩',
+        '≡', '±', '≥', '≤', '⌠', '⌡', '÷', '≈', '°', '∙', '·', '√', 'ⁿ', '²', '■', '\u{a0}',
+    ];
+    match byte {
+        0x00..=0x1f => LOW[byte as usize],
+        0x7f => '⌂',
+        0x20..=0x7e => byte as char,
+        _ => HIGH[byte as usize - 0x80],
+    }
+}
+
+/// EBCDIC code page 037; `None` for control bytes and invisible characters
+fn ebcdic(byte: u8) -> Option<char> {
+    const TABLE: [char; 192] = [
+        ' ', '.', 'â', 'ä', 'à', 'á', 'ã', 'å', 'ç', 'ñ', '¢', '.', '<', '(', '+', '|', // 0x40
+        '&', 'é', 'ê', 'ë', 'è', 'í', 'î', 'ï', 'ì', 'ß', '!', '$', '*', ')', ';', '¬', // 0x50
+        '-', '/', 'Â', 'Ä', 'À', 'Á', 'Ã', 'Å', 'Ç', 'Ñ', '¦', ',', '%', '_', '>', '?', // 0x60
+        'ø', 'É', 'Ê', 'Ë', 'È', 'Í', 'Î', 'Ï', 'Ì', '`', ':', '#', '@', '\'', '=', '"', // 0x70
+        'Ø', 'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', '«', '»', 'ð', 'ý', 'þ', '±', // 0x80
+        '°', 'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r', 'ª', 'º', 'æ', '¸', 'Æ', '¤', // 0x90
+        'µ', '~', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z', '¡', '¿', 'Ð', 'Ý', 'Þ', '®', // 0xa0
+        '^', '£', '¥', '·', '©', '§', '¶', '¼', '½', '¾', '[', ']', '¯', '¨', '´', '×', // 0xb0
+        '{', 'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', '.', 'ô', 'ö', 'ò', 'ó', 'õ', // 0xc0
+        '}', 'J', 'K', 'L', 'M', 'N', 'O', 'P', 'Q', 'R', '¹', 'û', 'ü', 'ù', 'ú', 'ÿ', // 0xd0
+        '\\', '÷', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z', '²', 'Ô', 'Ö', 'Ò', 'Ó', 'Õ', // 0xe0
+        '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', '³', 'Û', 'Ü', 'Ù', 'Ú', '.', // 0xf0
+    ];
+    // 0x41 (no-break space), 0xca (soft hyphen) and 0xff (EO) are invisible
+    match byte {
+        0x41 | 0xca | 0xff => None,
+        0x40..=0xff => Some(TABLE[byte as usize - 0x40]),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(encoding: Encoding, chunk: &[u8]) -> String {
+        encoding.glyphs(chunk).into_iter().map(|glyph| glyph.ch).collect()
+    }
+
+    #[test]
+    fn test_from_name() {
+        assert_eq!(Encoding::from_name("UTF-8"), Some(Encoding::Utf8));
+        assert_eq!(Encoding::from_name("iso-8859-1"), Some(Encoding::Latin1));
+        assert_eq!(Encoding::from_name("cp437"), Some(Encoding::Cp437));
+        assert_eq!(Encoding::from_name("cp037"), Some(Encoding::Ebcdic));
+        assert_eq!(Encoding::from_name("utf16"), Some(Encoding::Utf16Le));
+        assert_eq!(Encoding::from_name("utf_16be"), Some(Encoding::Utf16Be));
+        assert_eq!(Encoding::from_name("klingon"), None);
+    }
+
+    #[test]
+    fn test_ascii_and_latin1() {
+        assert_eq!(text(Encoding::Ascii, b"A\x00\xe9~"), "A..~");
+        assert_eq!(text(Encoding::Latin1, b"caf\xe9\x85\xad"), "café..");
+    }
+
+    #[test]
+    fn test_cp437() {
+        assert_eq!(text(Encoding::Cp437, b"\x01\x0aA\x7f\x80\xb0\xdb\xe1"), "☺◙A⌂Ç░█ß");
+        assert_eq!(text(Encoding::Cp437, b"\x00\xff"), "..");
+        assert_eq!(cp437(0xfe), '■');
+    }
+
+    #[test]
+    fn test_ebcdic() {
+        // "Hello, 123" in CP037
+        let hello = [0xc8, 0x85, 0x93, 0x93, 0x96, 0x6b, 0x40, 0xf1, 0xf2, 0xf3];
+        assert_eq!(text(Encoding::Ebcdic, &hello), "Hello, 123");
+        assert_eq!(text(Encoding::Ebcdic, &[0x00, 0x25, 0xff, 0x5b, 0xe0]), "...$\\");
+        assert_eq!(ebcdic(0x4a), Some('¢'));
+        assert_eq!(ebcdic(0xba), Some('['));
+    }
+
+    #[test]
+    fn test_utf8_split_sequences() {
+        let bytes = "a世b".as_bytes();
+        // Cut inside the 3-byte 世
+        assert_eq!(text(Encoding::Utf8, &bytes[..2]), "a·");
+        assert_eq!(text(Encoding::Utf8, &bytes[2..]), "··b");
+        assert_eq!(text(Encoding::Utf8, bytes), "a世b");
+        assert_eq!(text(Encoding::Utf8, b"ok\xff\x01"), "ok\u{fffd}.");
+    }
+
+    #[test]
+    fn test_glyph_bytes_cover_the_row() {
+        let utf8 = "é世\u{301}🎉".as_bytes();
+        for (encoding, chunk) in [
+            (Encoding::Utf8, &utf8[1..]),
+            (Encoding::Utf8, &b"a\xc0\xffz"[..]),
+            (Encoding::Utf16Le, &b"a\x00\x3d\xd8x"[..]),
+            (Encoding::Ebcdic, &b"\xc8\x85"[..]),
+        ] {
+            let glyphs = encoding.glyphs(chunk);
+            assert_eq!(glyphs.iter().map(|g| g.bytes).sum::<usize>(), chunk.len(), "{:?}", encoding);
+        }
+        let widths: Vec<usize> = Encoding::Utf8.glyphs("a世".as_bytes()).iter().map(|g| g.bytes).collect();
+        assert_eq!(widths, [1, 3]);
+    }
+
+    #[test]
+    fn test_utf16_units_span_their_bytes() {
+        let bytes: Vec<u8> = "Hi🎉".encode_utf16().flat_map(u16::to_le_bytes).collect();
+        let glyphs = Encoding::Utf16Le.glyphs(&bytes);
+        let spans: Vec<(char, usize)> = glyphs.iter().map(|g| (g.ch, g.cells)).collect();
+        assert_eq!(spans, vec![('H', 2), ('i', 2), ('🎉', 4)]);
+        assert_eq!(glyphs.iter().map(|g| g.cells).sum::<usize>(), bytes.len());
+
+        let be: Vec<u8> = "Ok\n\0".encode_utf16().flat_map(u16::to_be_bytes).collect();
+        assert_eq!(text(Encoding::Utf16Be, &be), "Ok..");
+        let classes: Vec<ByteClass> = Encoding::Utf16Be.glyphs(&be).iter().map(|g| g.class).collect();
+        assert_eq!(classes[2..], [ByteClass::Whitespace, ByteClass::Null]);
+    }
+
+    #[test]
+    fn test_utf16_split_pairs_and_odd_bytes() {
+        let bytes: Vec<u8> = "a🎉".encode_utf16().flat_map(u16::to_le_bytes).collect();
+        assert_eq!(text(Encoding::Utf16Le, &bytes[..4]), "a·");
+        assert_eq!(text(Encoding::Utf16Le, &bytes[4..]), "·");
+        assert_eq!(text(Encoding::Utf16Le, &bytes[..3]), "a·");
+        assert_eq!(text(Encoding::Utf16Le, &[0x00, 0xdc, 0x00, 0xdc]), "·\u{fffd}");
+    }
+}
@@ -1,8 +1,17 @@
 //! Hex dump display functionality
 
 use colored::{Color, Colorize};
+use serde_json::json;
+use std::fmt::Write as _;
 use std::io::{self, Write};
 
+use super::encoding::Encoding;
+use super::highlight::{highlight_at, Highlight};
+use super::html;
+use super::palette::ByteClass;
+use super::theme::Theme;
+use super::typed::DataType;
+
 /// Display data as a hex dump with optional colorization.
 ///
 /// Writes to stdout with a locked handle so the full dump is buffered
@@ -10,14 +19,15 @@ use std::io::{self, Write};
 ///
 /// # Arguments
 /// * `data` - The byte data to display
+/// * `offset` - Absolute offset of `data[0]` in the input, used for row labels
 /// * `width` - Number of bytes per line
 /// * `color` - Whether to colorize output
-/// * `utf8` - Whether to decode as UTF-8 (otherwise ASCII)
-pub fn display_hex(data: &[u8], width: usize, color: bool, utf8: bool) {
+/// * `encoding` - How the character column decodes bytes
+pub fn display_hex(data: &[u8], offset: u64, width: usize, color: bool, encoding: Encoding) {
     let stdout = io::stdout();
     let mut out = stdout.lock();
     // Ignore broken-pipe / write errors: nothing useful we can do from a CLI.
-    let _ = write_hex(&mut out, data, width, color, utf8);
+    let _ = write_hex(&mut out, data, offset, width, color, encoding);
 }
 
 /// Same as [`display_hex`] but writes to an arbitrary [`Write`]. Exposed for
@@ -25,92 +35,661 @@ pub fn display_hex(data: &[u8], width: usize, color: bool, utf8: bool) {
 pub fn write_hex<W: Write>(
     out: &mut W,
     data: &[u8],
+    offset: u64,
     width: usize,
     color: bool,
-    utf8: bool,
+    encoding: Encoding,
 ) -> io::Result<()> {
-    let colorize = |text: &str, col: Color| -> String {
-        if color {
-            text.color(col).to_string()
-        } else {
-            text.to_string()
+    let options = HexOptions { color, encoding, ..HexOptions::new(width) };
+    let mut writer = HexWriter::new(out, options, offset);
+    writer.set_end_offset(offset + data.len() as u64);
+    writer.write(data)?;
+    writer.finish()?;
+    Ok(())
+}
+
+/// Layout options shared by every hex row
+#[derive(Debug, Clone)]
+pub struct HexOptions {
+    /// Number of bytes per line
+    pub width: usize,
+    /// Whether to colorize output
+    pub color: bool,
+    /// How the character column decodes bytes
+    pub encoding: Encoding,
+    /// Whether to collapse runs of identical rows into a single `*` line
+    pub squeeze: bool,
+    /// Base each cell is written in
+    pub radix: Radix,
+    /// Bytes per cell: 1, 2, 4 or 8
+    pub word_size: usize,
+    /// Byte order used to read multi-byte cells and typed values
+    pub endian: Endian,
+    /// Type to decode each row as, shown in a column of its own
+    pub typed: Option<DataType>,
+    /// Show the typed column in place of the byte cells
+    pub typed_only: bool,
+    /// Colors for offsets, separators and byte classes when `color` is set
+    pub theme: Theme,
+    /// Byte ranges drawn on a background color, or bracketed without color
+    pub highlights: Vec<Highlight>,
+    /// Row layout; [`Style::Xxd`] ignores every option but `width`, and
+    /// [`Style::Json`] uses only `width`, `encoding`, `typed` and `endian`;
+    /// [`Style::Html`] ignores `color`
+    pub style: Style,
+}
+
+impl HexOptions {
+    /// Plain, uncolored hex/ASCII rows of `width` bytes with every row shown
+    pub fn new(width: usize) -> Self {
+        HexOptions {
+            width,
+            color: false,
+            encoding: Encoding::Ascii,
+            squeeze: false,
+            radix: Radix::Hex,
+            word_size: 1,
+            endian: Endian::Little,
+            typed: None,
+            typed_only: false,
+            theme: Theme::default(),
+            highlights: Vec::new(),
+            style: Style::Hhead,
         }
+    }
+}
+
+/// Overall row layout
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    /// Offset, grouped cells, optional typed column and a `|`-framed character column
+    Hhead,
+    /// `xxd`'s plain layout: two-byte groups and an unframed ASCII column
+    Xxd,
+    /// One NDJSON record per row: `{"type":"row","offset":..,"bytes":[..],"text":".."}`
+    Json,
+    /// The hhead layout as HTML spans, for the body of an HTML report
+    Html,
+}
+
+/// Coloring of a hhead-layout row
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Markup {
+    Plain,
+    /// ANSI escapes, when `color` is set
+    Ansi,
+    /// HTML spans styled by the classes of [`write_html_head`](super::html::write_html_head)
+    Html,
+}
+
+impl Style {
+    /// Parse a style name: `hhead` or `xxd`; [`Style::Json`] and [`Style::Html`] come from `--format`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "hhead" | "default" => Some(Style::Hhead),
+            "xxd" => Some(Style::Xxd),
+            _ => None,
+        }
+    }
+}
+
+/// Write the line that introduces one `--range` region, in `options.style`
+///
+/// Text styles get `--- 0x1000..0x1100 (256 bytes) ---` in the separator
+/// color, JSON a `{"type":"region","start":..,"end":..}` record and HTML a
+/// heading for the region's own `<pre>`. An `end` of `None` is a region
+/// still being read from a stream: `--- 0x1000.. ---`, or `"end": null`.
+pub fn write_region_header<W: Write>(
+    out: &mut W,
+    start: u64,
+    end: Option<u64>,
+    options: &HexOptions,
+) -> io::Result<()> {
+    let text = match end {
+        Some(end) => format!("--- 0x{:x}..0x{:x} ({} bytes) ---", start, end, end - start),
+        None => format!("--- 0x{:x}.. ---", start),
     };
+    match options.style {
+        Style::Json => writeln!(out, "{}", json!({ "type": "region", "start": start, "end": end })),
+        Style::Html => writeln!(out, "<h3>{}</h3>", html::span("separator", &text)),
+        _ if options.color => writeln!(out, "{}", text.color(options.theme.separator)),
+        _ => writeln!(out, "{}", text),
+    }
+}
+
+/// Base used for the byte cells; offsets stay in hex
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Radix {
+    Hex,
+    Octal,
+    Decimal,
+    Binary,
+}
+
+impl Radix {
+    /// Parse a radix name: `hex`, `oct`, `dec` or `bin` (or the long forms)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "hex" | "x" | "16" => Some(Radix::Hex),
+            "oct" | "octal" | "o" | "8" => Some(Radix::Octal),
+            "dec" | "decimal" | "d" | "10" => Some(Radix::Decimal),
+            "bin" | "binary" | "b" | "2" => Some(Radix::Binary),
+            _ => None,
+        }
+    }
+
+    /// Characters needed for any `size`-byte value, so every cell has the same width
+    pub fn digits(self, size: usize) -> usize {
+        let bits = 8 * size as u32;
+        match self {
+            Radix::Hex => 2 * size,
+            Radix::Octal => bits.div_ceil(3) as usize,
+            Radix::Decimal => (u64::MAX >> (64 - bits)).to_string().len(),
+            Radix::Binary => bits as usize,
+        }
+    }
 
-    let group_size = 8;
-    let num_groups = width.div_ceil(group_size);
+    /// Numeric base, as taken by `from_str_radix`
+    pub fn base(self) -> u32 {
+        match self {
+            Radix::Hex => 16,
+            Radix::Octal => 8,
+            Radix::Decimal => 10,
+            Radix::Binary => 2,
+        }
+    }
 
-    // Pick an offset width wide enough for the largest offset we'll print.
-    // Default to 8 hex digits; grow to 16 for >4GiB inputs.
-    let max_offset = data.len().saturating_sub(1);
-    let offset_width = if max_offset > u32::MAX as usize { 16 } else { 8 };
+    /// Format one byte as a fixed-width cell
+    pub fn format(self, byte: u8) -> String {
+        self.format_word(u64::from(byte), 1)
+    }
 
-    for (i, chunk) in data.chunks(width).enumerate() {
-        let offset = i * width;
-        write!(
+    /// Format a `size`-byte value as a fixed-width cell
+    pub fn format_word(self, value: u64, size: usize) -> String {
+        let mut cell = String::with_capacity(self.digits(size));
+        self.push_word(&mut cell, value, size);
+        cell
+    }
+
+    /// Append a `size`-byte value to `line` as a fixed-width cell
+    pub fn push_word(self, line: &mut String, value: u64, size: usize) {
+        const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+        let digits = self.digits(size);
+        // Writing to a `String` cannot fail
+        let _ = match self {
+            // The common case, spelled out to keep the formatting machinery
+            // out of the per-byte path
+            Radix::Hex => {
+                line.extend((0..digits).rev().map(|i| HEX_DIGITS[(value >> (4 * i) & 0xf) as usize] as char));
+                Ok(())
+            }
+            Radix::Octal => write!(line, "{:0digits$o}", value),
+            Radix::Decimal => write!(line, "{:digits$}", value),
+            Radix::Binary => write!(line, "{:0digits$b}", value),
+        };
+    }
+}
+
+/// Byte order of multi-byte cells
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endian {
+    Little,
+    Big,
+}
+
+impl Endian {
+    /// Parse an endianness name: `le`/`little` or `be`/`big`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "le" | "little" => Some(Endian::Little),
+            "be" | "big" => Some(Endian::Big),
+            _ => None,
+        }
+    }
+
+    /// Read up to 8 bytes as an unsigned integer in this byte order
+    pub fn read(self, bytes: &[u8]) -> u64 {
+        let fold = |value: u64, &byte: &u8| (value << 8) | u64::from(byte);
+        match self {
+            Endian::Little => bytes.iter().rev().fold(0, fold),
+            Endian::Big => bytes.iter().fold(0, fold),
+        }
+    }
+
+    /// Lay out the low `size` bytes of `value` in this byte order, the inverse of [`read`](Self::read)
+    pub fn write(self, value: u64, size: usize) -> Vec<u8> {
+        let bytes = value.to_le_bytes()[..size].to_vec();
+        match self {
+            Endian::Little => bytes,
+            Endian::Big => bytes.into_iter().rev().collect(),
+        }
+    }
+}
+
+/// Incremental hex dump writer
+///
+/// Accepts input in arbitrarily sized pieces and emits complete rows as soon
+/// as they are available, holding back at most one partial row. Offsets and
+/// column alignment are therefore identical to a single [`write_hex`] call
+/// over the concatenated input, while memory use stays bounded by `width`.
+pub struct HexWriter<W: Write> {
+    out: W,
+    options: HexOptions,
+    // Offset of the first row, which rows are aligned to, and of the next
+    origin: u64,
+    offset: u64,
+    offset_width: usize,
+    pending: Vec<u8>,
+    // Last full row shown (or squeezed), and whether we are inside a `*` run
+    previous: Vec<u8>,
+    squeezing: bool,
+    // Reused buffers for the row being built and the cell within it, so
+    // plain rows are written without allocating per byte
+    line: String,
+    cell: String,
+}
+
+impl<W: Write> HexWriter<W> {
+    /// Create a writer whose first row is labelled `offset`
+    pub fn new(out: W, options: HexOptions, offset: u64) -> Self {
+        let pending = Vec::with_capacity(options.width);
+        HexWriter {
             out,
-            "{}:",
-            colorize(&format!("{:0width$x}", offset, width = offset_width), Color::Cyan)
-        )?;
-
-        for group in 0..num_groups {
-            let start = group * group_size;
-            let end = (start + group_size).min(chunk.len());
-            if start < chunk.len() {
-                for &byte in &chunk[start..end] {
-                    write!(out, " {:02x}", byte)?;
+            options,
+            origin: offset,
+            offset,
+            offset_width: offset_digits(offset),
+            pending,
+            previous: Vec::new(),
+            squeezing: false,
+            line: String::new(),
+            cell: String::new(),
+        }
+    }
+
+    /// Declare where the dump will end so every row uses the same offset
+    /// width. Without it the width grows on the fly once offsets pass 4 GiB.
+    pub fn set_end_offset(&mut self, end: u64) {
+        self.offset_width = self.offset_width.max(offset_digits(end.saturating_sub(1)));
+    }
+
+    /// Absolute offset of the next byte to be written
+    pub fn offset(&self) -> u64 {
+        self.offset + self.pending.len() as u64
+    }
+
+    /// Feed more input, writing every row that is now complete
+    pub fn write(&mut self, mut data: &[u8]) -> io::Result<()> {
+        let width = self.options.width;
+        // After `flush_pending` the row under way ends early, back on the grid
+        let row_len = width - ((self.offset - self.origin) % width as u64) as usize;
+        if !self.pending.is_empty() || row_len < width {
+            let take = (row_len - self.pending.len()).min(data.len());
+            self.pending.extend_from_slice(&data[..take]);
+            data = &data[take..];
+            if self.pending.len() < row_len {
+                return Ok(());
+            }
+            let row = std::mem::take(&mut self.pending);
+            self.emit_row(&row)?;
+            self.pending = row;
+            self.pending.clear();
+        }
+        let mut rows = data.chunks_exact(width);
+        for row in &mut rows {
+            self.emit_row(row)?;
+        }
+        self.pending.extend_from_slice(rows.remainder());
+        Ok(())
+    }
+
+    /// Returns true if a partial row is being held back
+    pub fn has_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Write the partial row now instead of waiting for it to fill
+    ///
+    /// The next row then starts at the following byte and only runs to
+    /// where this one would have ended, so later rows line up with the
+    /// `width` grid from the first offset again.
+    pub fn flush_pending(&mut self) -> io::Result<()> {
+        if !self.pending.is_empty() {
+            let row = std::mem::take(&mut self.pending);
+            self.emit_row(&row)?;
+        }
+        Ok(())
+    }
+
+    /// Flush the underlying writer
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+
+    /// Mutable access to the underlying writer, e.g. to drain rows buffered
+    /// in a `Vec`
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.out
+    }
+
+    /// Write the trailing partial row, if any, and return the inner writer
+    ///
+    /// Like `hexdump`, a dump that ends inside a `*` run closes it with a
+    /// line holding just the end offset, so the length is always visible.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.flush_pending()?;
+        if self.squeezing {
+            let end = format!("{:0width$x}", self.offset, width = self.offset_width.max(offset_digits(self.offset)));
+            match self.markup() {
+                Markup::Plain => writeln!(self.out, "{}", end)?,
+                Markup::Ansi => writeln!(self.out, "{}", end.color(self.options.theme.offset))?,
+                Markup::Html => writeln!(self.out, "{}", html::span("offset", &end))?,
+            }
+        }
+        Ok(self.out)
+    }
+
+    /// Show one row at the current offset, or fold it into a `*` run
+    fn emit_row(&mut self, row: &[u8]) -> io::Result<()> {
+        let row_offset = self.offset;
+        self.offset += row.len() as u64;
+        if !self.options.squeeze || matches!(self.options.style, Style::Xxd | Style::Json) {
+            return self.write_row(row_offset, row);
+        }
+        // Only full rows take part; a partial row always ends the run, and a
+        // row with highlighted bytes is always shown
+        let highlighted = self.options.highlights.iter().any(|h| {
+            h.start < row_offset + row.len() as u64 && row_offset < h.start.saturating_add(h.len)
+        });
+        if row.len() == self.options.width && self.previous == row && !highlighted {
+            if !self.squeezing {
+                match self.markup() {
+                    Markup::Plain => writeln!(self.out, "*")?,
+                    Markup::Ansi => writeln!(self.out, "{}", "*".color(self.options.theme.separator))?,
+                    Markup::Html => writeln!(self.out, "{}", html::span("separator", "*"))?,
+                }
+                self.squeezing = true;
+            }
+            return Ok(());
+        }
+        self.squeezing = false;
+        self.previous.clear();
+        if row.len() == self.options.width {
+            self.previous.extend_from_slice(row);
+        }
+        self.write_row(row_offset, row)
+    }
+
+    /// How [`Style::Hhead`]-layout rows mark up their colors
+    fn markup(&self) -> Markup {
+        match self.options.style {
+            Style::Html => Markup::Html,
+            _ if self.options.color => Markup::Ansi,
+            _ => Markup::Plain,
+        }
+    }
+
+    fn write_row(&mut self, row_offset: u64, chunk: &[u8]) -> io::Result<()> {
+        match self.options.style {
+            Style::Hhead | Style::Html => {}
+            Style::Xxd => return self.write_xxd_row(row_offset, chunk),
+            Style::Json => return self.write_json_row(row_offset, chunk),
+        }
+        let HexOptions { width, encoding, radix, word_size, endian, typed, typed_only, theme, .. } = self.options;
+        let markup = self.markup();
+        let palette = theme.palette;
+        let highlights = &self.options.highlights;
+        // The row is built in `line` and written in one go; writing to a
+        // `String` cannot fail, so those results are ignored
+        let line = &mut self.line;
+        let cell = &mut self.cell;
+        line.clear();
+        let colorize = |line: &mut String, text: &str, col: Color, class: &str| match markup {
+            Markup::Plain => line.push_str(text),
+            Markup::Ansi => {
+                let _ = write!(line, "{}", text.color(col));
+            }
+            Markup::Html => line.push_str(&html::span(class, text)),
+        };
+        // Cells and glyphs take their byte class's color; a multi-byte cell
+        // is only colored when all of its bytes share a class. Highlighted
+        // ones are drawn in black on the highlight color instead. In HTML
+        // each also carries its offset and value for hovering.
+        let paint = |line: &mut String,
+                     text: &str,
+                     bytes: &[u8],
+                     offset: u64,
+                     value: u64,
+                     background: Option<Color>| {
+            if markup == Markup::Plain {
+                return line.push_str(text);
+            }
+            let class = ByteClass::of(bytes[0]);
+            let uniform = bytes.iter().all(|&b| ByteClass::of(b) == class);
+            let _ = match (markup, background) {
+                (Markup::Ansi, Some(background)) => write!(line, "{}", text.black().on_color(background)),
+                (Markup::Ansi, None) if uniform => write!(line, "{}", text.color(palette.color(class))),
+                (Markup::Html, _) => {
+                    let value = value.to_string();
+                    write!(line, "{}", html::cell(text, uniform.then_some(class), background, offset, &value))
+                }
+                _ => write!(line, "{}", text),
+            };
+        };
+        // Highlight covering any of `len` bytes from `index` in this row
+        let background = |index: usize, len: usize| -> Option<Color> {
+            if highlights.is_empty() {
+                return None;
+            }
+            let start = row_offset + index as u64;
+            (start..start + len as u64).find_map(|offset| highlight_at(highlights, offset, theme.highlight))
+        };
+
+        // Groups hold 8 bytes, i.e. 8 / word_size cells
+        let group_size = 8;
+        let num_groups = width.div_ceil(group_size);
+        // A cell is a separating space plus the radix's fixed digit count
+        let cell_width = radix.digits(word_size);
+        let blank = " ".repeat(cell_width + 1);
+
+        self.offset_width = self.offset_width.max(offset_digits(row_offset));
+        cell.clear();
+        let _ = write!(cell, "{:0width$x}", row_offset, width = self.offset_width);
+        colorize(line, cell, theme.offset, "offset");
+        line.push(':');
+
+        if !(typed_only && typed.is_some()) {
+            // Without color, highlighted runs are bracketed in the spaces
+            // around their cells, closing at the end of each group
+            let mut marked = false;
+            let close = |marked: &mut bool| if std::mem::take(marked) { "]" } else { " " };
+            for group in 0..num_groups {
+                let start = group * group_size;
+                let end = (start + group_size).min(chunk.len());
+                if start < chunk.len() {
+                    // A partial trailing word shows only the bytes it has,
+                    // right-aligned in a full-width cell
+                    let mut cells = 0;
+                    for (i, word) in chunk[start..end].chunks(word_size).enumerate() {
+                        let index = start + i * word_size;
+                        let value = endian.read(word);
+                        cell.clear();
+                        for _ in radix.digits(word.len())..cell_width {
+                            cell.push(' ');
+                        }
+                        radix.push_word(cell, value, word.len());
+                        let background = background(index, word.len());
+                        let lead = match (marked, background.is_some()) {
+                            _ if markup != Markup::Plain => " ",
+                            (false, true) => "[",
+                            (true, false) => "]",
+                            _ => " ",
+                        };
+                        marked = markup == Markup::Plain && background.is_some();
+                        line.push_str(lead);
+                        paint(line, cell, word, row_offset + index as u64, value, background);
+                        cells += 1;
+                    }
+                    for _ in cells..group_size.div_ceil(word_size) {
+                        line.push_str(close(&mut marked));
+                        line.push_str(&blank[1..]);
+                    }
+                    line.push_str(close(&mut marked));
+                } else {
+                    for _ in 0..group_size.div_ceil(word_size) {
+                        line.push_str(&blank);
+                    }
+                    line.push(' ');
+                }
+            }
+        }
+
+        // Typed values, one per whole element; a partial trailing element is
+        // marked with `-` rather than decoded from missing bytes.
+        if let Some(typed) = typed {
+            let cell_width = typed.cell_width();
+            for element in 0..width / typed.size() {
+                let start = element * typed.size();
+                let cell = match chunk.get(start..start + typed.size()) {
+                    Some(bytes) => typed.format(bytes, endian),
+                    None if start < chunk.len() => "-".to_string(),
+                    None => String::new(),
+                };
+                let _ = write!(line, " {:>cell_width$}", cell);
+            }
+            line.push(' ');
+        }
+
+        line.push(' ');
+        colorize(line, "|", theme.separator, "separator");
+
+        // Character representation, padded by terminal cells rather than
+        // chars so wide and combining characters keep the '|' aligned.
+        // Without color, highlighted runs are bracketed here too, taking
+        // the padding of a short row or widening a full one.
+        let glyphs = encoding.glyphs(chunk);
+        let mut index = 0;
+        let mut cells = 0;
+        let mut marked = false;
+        for glyph in &glyphs {
+            let background = background(index, glyph.bytes);
+            let bytes = chunk.get(index..index + glyph.bytes).unwrap_or_default();
+            let offset = row_offset + index as u64;
+            index += glyph.bytes;
+            let width = glyph.width();
+            cells += glyph.cells.max(width);
+            let padding = glyph.cells.saturating_sub(width);
+            match (markup, background) {
+                (Markup::Ansi, Some(background)) => {
+                    let text = format!("{}{:padding$}", glyph.ch, "");
+                    let _ = write!(line, "{}", text.black().on_color(background));
                 }
-                for _ in end..start + group_size {
-                    write!(out, "   ")?;
+                (Markup::Ansi, None) => {
+                    let _ = write!(line, "{}{:padding$}", glyph.ch.to_string().color(palette.color(glyph.class)), "");
                 }
-                write!(out, " ")?;
-            } else {
-                for _ in 0..group_size {
-                    write!(out, "   ")?;
+                (Markup::Html, _) => {
+                    let value: Vec<String> = bytes.iter().map(u8::to_string).collect();
+                    let ch = glyph.ch.to_string();
+                    let text = html::cell(&ch, Some(glyph.class), background, offset, &value.join(" "));
+                    let _ = write!(line, "{}{:padding$}", text, "");
+                }
+                (Markup::Plain, background) => {
+                    if marked != background.is_some() {
+                        line.push(if marked { ']' } else { '[' });
+                        marked = !marked;
+                        cells += 1;
+                    }
+                    line.push(glyph.ch);
+                    for _ in 0..padding {
+                        line.push(' ');
+                    }
                 }
-                write!(out, " ")?;
             }
         }
+        if marked {
+            line.push(']');
+            cells += 1;
+        }
+        for _ in cells..width {
+            line.push(' ');
+        }
+        line.push(' ');
+        colorize(line, "|", theme.separator, "separator");
+        line.push('\n');
+        self.out.write_all(line.as_bytes())
+    }
 
-        write!(out, " {}", colorize("|", Color::Magenta))?;
+    /// One row exactly as plain `xxd -c width` prints it: the hex area is
+    /// padded to full width and the ASCII column follows two spaces later
+    fn write_xxd_row(&mut self, row_offset: u64, chunk: &[u8]) -> io::Result<()> {
+        let width = self.options.width;
+        // xxd pads offsets to 8 digits and lets longer ones grow
+        let mut line = format!("{:08x}: ", row_offset);
+        for i in 0..width {
+            match chunk.get(i) {
+                Some(byte) => line.push_str(&format!("{:02x}", byte)),
+                None => line.push_str("  "),
+            }
+            if i % 2 == 1 || i + 1 == width {
+                line.push(' ');
+            }
+        }
+        line.push(' ');
+        line.extend(chunk.iter().map(|&b| if (0x20..=0x7e).contains(&b) { b as char } else { '.' }));
+        writeln!(self.out, "{}", line)
+    }
 
-        // Character representation. Note: in UTF-8 mode the trailing '|'
-        // column is only aligned for ASCII input, since wide / combining
-        // characters don't map 1:1 to terminal cells.
-        let repr: String = if utf8 {
-            String::from_utf8_lossy(chunk)
-                .chars()
-                .map(|c| if c.is_control() { '.' } else { c })
-                .collect()
-        } else {
+    /// One row as a single-line JSON record; `text` is the character column
+    /// as hhead would show it, and `values` the `--as` column, or null
+    fn write_json_row(&mut self, row_offset: u64, chunk: &[u8]) -> io::Result<()> {
+        let HexOptions { encoding, endian, typed, .. } = self.options;
+        let text: String = encoding.glyphs(chunk).iter().map(|glyph| glyph.ch).collect();
+        let values: Option<Vec<String>> = typed.map(|typed| {
             chunk
-                .iter()
-                .map(|&b| if (32..=126).contains(&b) { b as char } else { '.' })
+                .chunks(typed.size())
+                .filter(|bytes| bytes.len() == typed.size())
+                .map(|bytes| typed.format(bytes, endian))
                 .collect()
-        };
-        let repr_len = repr.chars().count();
-        write!(out, "{}", repr)?;
-        for _ in repr_len..width {
-            write!(out, " ")?;
-        }
-        writeln!(out, " {}", colorize("|", Color::Magenta))?;
+        });
+        let record = json!({ "type": "row", "offset": row_offset, "bytes": chunk, "text": text, "values": values });
+        writeln!(self.out, "{}", record)
     }
-    Ok(())
+}
+
+/// Offsets use 8 hex digits, growing to 16 once they no longer fit in 32 bits
+fn offset_digits(offset: u64) -> usize {
+    if offset > u64::from(u32::MAX) { 16 } else { 8 }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::display::palette::Palette;
+    use crate::display::with_color_override;
+    use unicode_width::UnicodeWidthChar;
 
-    fn capture(data: &[u8], width: usize, color: bool, utf8: bool) -> String {
+    fn capture_hex(data: &[u8], width: usize, color: bool, encoding: Encoding) -> String {
         let mut buf = Vec::new();
-        write_hex(&mut buf, data, width, color, utf8).expect("write_hex should not fail");
+        write_hex(&mut buf, data, 0, width, color, encoding).expect("write_hex should not fail");
         String::from_utf8(buf).expect("output should be valid utf-8")
     }
 
+    /// Dump `data` from offset 0 with `options`, fed in small pieces so rows
+    /// also cross write boundaries
+    fn capture(data: &[u8], options: HexOptions) -> String {
+        let mut buf = Vec::new();
+        let mut writer = HexWriter::new(&mut buf, options, 0);
+        writer.set_end_offset(data.len() as u64);
+        for chunk in data.chunks(5) {
+            writer.write(chunk).unwrap();
+        }
+        writer.finish().unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
     #[test]
     fn test_display_hex_basic() {
-        let out = capture(b"Hello, World!", 16, false, false);
+        let out = capture_hex(b"Hello, World!", 16, false, Encoding::Ascii);
         assert!(out.starts_with("00000000:"), "offset header missing: {out}");
         assert!(out.contains(" 48 65 6c 6c 6f"), "hex bytes missing: {out}");
         assert!(out.contains("|Hello, World!"), "ascii column missing: {out}");
@@ -118,19 +697,27 @@ mod tests {
 
     #[test]
     fn test_display_hex_empty() {
-        let out = capture(b"", 16, false, false);
+        let out = capture_hex(b"", 16, false, Encoding::Ascii);
         assert!(out.is_empty(), "empty input should produce empty output");
     }
 
     #[test]
     fn test_display_hex_utf8_preserves_printable_multibyte() {
-        let out = capture("Hi 世".as_bytes(), 16, false, true);
+        let out = capture_hex("Hi 世".as_bytes(), 16, false, Encoding::Utf8);
         assert!(out.contains("世"), "utf-8 char missing: {out}");
     }
 
+    #[test]
+    fn test_display_hex_other_encodings() {
+        let out = capture_hex(b"\x01\xc8\x81", 16, false, Encoding::Cp437);
+        assert!(out.contains("|☺╚ü"), "cp437 glyphs missing: {out}");
+        let out = capture_hex(b"\xc8\x85\x93\x93\x96", 16, false, Encoding::Ebcdic);
+        assert!(out.contains("|Hello"), "ebcdic text missing: {out}");
+    }
+
     #[test]
     fn test_display_hex_ascii_mode_replaces_nonprintable() {
-        let out = capture(b"\x01\x02A", 16, false, false);
+        let out = capture_hex(b"\x01\x02A", 16, false, Encoding::Ascii);
         assert!(out.contains("|..A"), "control bytes should be dots: {out}");
     }
 
@@ -138,9 +725,7 @@ mod tests {
     fn test_display_hex_color_contains_ansi() {
         // The `colored` crate auto-disables ANSI for non-TTY writers; override it
         // so this test sees escape sequences regardless of how cargo captures stdio.
-        colored::control::set_override(true);
-        let out = capture(b"Test", 16, true, false);
-        colored::control::unset_override();
+        let out = with_color_override(true, || capture_hex(b"Test", 16, true, Encoding::Ascii));
         assert!(out.contains("\x1b["), "colored output should contain ANSI escape: {out}");
     }
 
@@ -150,8 +735,464 @@ mod tests {
         // Avoid allocating 4 GiB by using a simple repeat-only slice semantic:
         // we can't cheaply *create* >u32::MAX bytes, so instead verify the
         // default 8-digit path is stable for the typical case.
-        let out = capture(&[0u8; 32], 16, false, false);
+        let out = capture_hex(&[0u8; 32], 16, false, Encoding::Ascii);
         assert!(out.contains("00000000:"));
         assert!(out.contains("00000010:"));
     }
+
+    #[test]
+    fn test_display_hex_uses_absolute_offset() {
+        let mut buf = Vec::new();
+        write_hex(&mut buf, &[0u8; 32], 0x10000, 16, false, Encoding::Ascii).unwrap();
+        let out = String::from_utf8(buf).unwrap();
+        assert!(out.starts_with("00010000:"), "first row should carry the base offset: {out}");
+        assert!(out.contains("\n00010010:"), "second row should continue from it: {out}");
+    }
+
+    #[test]
+    fn test_display_hex_offset_past_4gib_uses_16_digits() {
+        let mut buf = Vec::new();
+        write_hex(&mut buf, b"abc", 0x1_0000_0000, 16, false, Encoding::Ascii).unwrap();
+        let out = String::from_utf8(buf).unwrap();
+        assert!(out.starts_with("0000000100000000:"), "{out}");
+    }
+
+    #[test]
+    fn test_hex_writer_matches_single_call_across_chunk_boundaries() {
+        let data: Vec<u8> = (0..=255u8).cycle().take(1000).collect();
+        let expected = capture_hex(&data, 24, false, Encoding::Ascii);
+
+        for piece in [1, 7, 24, 25, 333] {
+            let mut buf = Vec::new();
+            let options = HexOptions::new(24);
+            let mut writer = HexWriter::new(&mut buf, options, 0);
+            writer.set_end_offset(data.len() as u64);
+            for chunk in data.chunks(piece) {
+                writer.write(chunk).unwrap();
+            }
+            writer.finish().unwrap();
+            assert_eq!(String::from_utf8(buf).unwrap(), expected, "piece size {piece}");
+        }
+    }
+
+    #[test]
+    fn test_hex_writer_holds_back_partial_row() {
+        let mut buf = Vec::new();
+        let options = HexOptions::new(16);
+        let mut writer = HexWriter::new(&mut buf, options, 0x100);
+        writer.write(b"0123456789").unwrap();
+        assert_eq!(writer.offset(), 0x10a);
+        writer.write(b"abcdef").unwrap();
+        writer.write(b"XYZ").unwrap();
+        writer.finish().unwrap();
+        let out = String::from_utf8(buf).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 2, "{out}");
+        assert!(lines[0].starts_with("00000100: 30 31"), "{out}");
+        assert!(lines[0].ends_with("|0123456789abcdef |"), "{out}");
+        assert!(lines[1].starts_with("00000110: 58 59 5a"), "{out}");
+    }
+
+    #[test]
+    fn test_hex_writer_flush_pending_keeps_offsets() {
+        let mut buf = Vec::new();
+        let options = HexOptions::new(16);
+        let mut writer = HexWriter::new(&mut buf, options, 0);
+        writer.write(b"abc").unwrap();
+        assert!(writer.has_pending());
+        writer.flush_pending().unwrap();
+        assert!(!writer.has_pending());
+        // The row after a flushed one is cut short to get back on the grid
+        writer.write(b"0123456789abc").unwrap();
+        assert!(!writer.has_pending());
+        writer.write(b"defXY").unwrap();
+        writer.flush_pending().unwrap();
+        writer.write(b"0123456789abcdefghijk").unwrap();
+        writer.finish().unwrap();
+        let out = String::from_utf8(buf).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 5, "{out}");
+        assert!(lines[0].starts_with("00000000: 61 62 63 "), "{out}");
+        assert!(lines[1].starts_with("00000003: 30 31"), "{out}");
+        assert!(lines[2].starts_with("00000010: 64 65 66 58 59 "), "{out}");
+        assert!(lines[3].starts_with("00000015: 30 31"), "{out}");
+        assert!(lines[4].starts_with("00000020: 62 63 64"), "{out}");
+    }
+
+    #[test]
+    fn test_hex_writer_flush_pending_realigns_to_first_offset() {
+        let mut buf = Vec::new();
+        let mut writer = HexWriter::new(&mut buf, HexOptions::new(8), 3);
+        writer.write(b"ab").unwrap();
+        writer.flush_pending().unwrap();
+        writer.write(b"0123456789").unwrap();
+        writer.finish().unwrap();
+        let out = String::from_utf8(buf).unwrap();
+        let offsets: Vec<&str> = out.lines().map(|line| &line[..8]).collect();
+        assert_eq!(offsets, ["00000003", "00000005", "0000000b"], "{out}");
+    }
+
+    #[test]
+    fn test_squeeze_collapses_identical_rows() {
+        let mut data = vec![0xAAu8; 16];
+        data.extend_from_slice(&[0u8; 16 * 100]);
+        data.extend_from_slice(b"0123456789abcdefXYZ");
+        let out = capture(&data, HexOptions { squeeze: true, ..HexOptions::new(16) });
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 5, "{out}");
+        assert!(lines[0].starts_with("00000000: aa aa"), "{out}");
+        assert!(lines[1].starts_with("00000010: 00 00"), "{out}");
+        assert_eq!(lines[2], "*");
+        assert!(lines[3].starts_with("00000650: 30 31"), "next differing row keeps its offset: {out}");
+        assert!(lines[4].starts_with("00000660: 58 59 5a"), "{out}");
+    }
+
+    #[test]
+    fn test_squeeze_run_at_end_closes_with_end_offset() {
+        let out = capture(&[0u8; 16 * 4], HexOptions { squeeze: true, ..HexOptions::new(16) });
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 3, "{out}");
+        assert!(lines[0].starts_with("00000000: 00"), "{out}");
+        assert_eq!(lines[1], "*");
+        assert_eq!(lines[2], "00000040");
+    }
+
+    #[test]
+    fn test_squeeze_two_identical_rows() {
+        let out = capture(&[7u8; 32], HexOptions { squeeze: true, ..HexOptions::new(16) });
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines, vec![lines[0], "*", "00000020"], "{out}");
+    }
+
+    #[test]
+    fn test_squeeze_off_prints_every_row() {
+        let out = capture_hex(&[0u8; 16 * 4], 16, false, Encoding::Ascii);
+        assert_eq!(out.lines().count(), 4);
+        assert!(!out.contains('*'));
+    }
+
+    #[test]
+    fn test_radix_cells() {
+        let data = [0x00, 0x07, 0x41, 0xff];
+        let cells = |radix| capture(&data, HexOptions { radix, ..HexOptions::new(4) });
+        assert!(cells(Radix::Octal).starts_with("00000000: 000 007 101 377 "));
+        assert!(cells(Radix::Decimal).starts_with("00000000:   0   7  65 255 "));
+        assert!(cells(Radix::Binary).starts_with("00000000: 00000000 00000111 01000001 11111111 "));
+    }
+
+    #[test]
+    fn test_radix_keeps_char_column_aligned() {
+        for radix in [Radix::Hex, Radix::Octal, Radix::Decimal, Radix::Binary] {
+            let out = capture(b"0123456789abcdefXYZ", HexOptions { radix, ..HexOptions::new(16) });
+            let bars: Vec<usize> = out.lines().map(|line| line.find('|').unwrap()).collect();
+            assert_eq!(bars.len(), 2);
+            assert_eq!(bars[0], bars[1], "partial row misaligned for {:?}:\n{}", radix, out);
+            assert!(out.lines().all(|line| line.ends_with('|')));
+            let tails: Vec<usize> = out.lines().map(|line| line.rfind('|').unwrap()).collect();
+            assert_eq!(tails[0], tails[1], "{:?}", radix);
+        }
+    }
+
+    #[test]
+    fn test_radix_from_name() {
+        assert_eq!(Radix::from_name("hex"), Some(Radix::Hex));
+        assert_eq!(Radix::from_name("OCT"), Some(Radix::Octal));
+        assert_eq!(Radix::from_name("dec"), Some(Radix::Decimal));
+        assert_eq!(Radix::from_name("binary"), Some(Radix::Binary));
+        assert_eq!(Radix::from_name("base64"), None);
+    }
+
+    #[test]
+    fn test_word_cells_little_and_big_endian() {
+        let data: Vec<u8> = (0..16).collect();
+        let le = capture(&data, HexOptions { word_size: 4, endian: Endian::Little, ..HexOptions::new(16) });
+        assert!(le.starts_with("00000000: 03020100 07060504  0b0a0908 0f0e0d0c  |"), "{le}");
+        let be = capture(&data, HexOptions { word_size: 2, endian: Endian::Big, ..HexOptions::new(16) });
+        assert!(be.starts_with("00000000: 0001 0203 0405 0607  0809 0a0b 0c0d 0e0f  |"), "{be}");
+        let q = capture(&data, HexOptions { word_size: 8, endian: Endian::Little, ..HexOptions::new(16) });
+        assert!(q.starts_with("00000000: 0706050403020100  0f0e0d0c0b0a0908  |"), "{q}");
+    }
+
+    #[test]
+    fn test_word_partial_trailing_word() {
+        let data = b"ABCDEFGHIJKLMNOPQRS";
+        let out = capture(data, HexOptions { word_size: 4, endian: Endian::Little, ..HexOptions::new(16) });
+        let lines: Vec<&str> = out.lines().collect();
+        assert!(lines[1].starts_with("00000010:   535251          "), "{out}");
+        assert_eq!(lines[0].find('|'), lines[1].find('|'), "{out}");
+        assert!(lines[1].ends_with("|QRS              |"), "{out}");
+    }
+
+    #[test]
+    fn test_word_cells_in_other_radixes() {
+        let data = [0xff, 0xff, 0x01, 0x00];
+        let out = capture(&data, HexOptions { word_size: 2, radix: Radix::Decimal, ..HexOptions::new(4) });
+        assert!(out.starts_with("00000000: 65535     1 "));
+    }
+
+    #[test]
+    fn test_radix_word_digits() {
+        assert_eq!(Radix::Hex.digits(4), 8);
+        assert_eq!(Radix::Octal.digits(2), 6);
+        assert_eq!(Radix::Decimal.digits(1), 3);
+        assert_eq!(Radix::Decimal.digits(8), 20);
+        assert_eq!(Radix::Binary.digits(2), 16);
+    }
+
+    #[test]
+    fn test_endian_read() {
+        assert_eq!(Endian::Little.read(&[0x01, 0x02]), 0x0201);
+        assert_eq!(Endian::Big.read(&[0x01, 0x02]), 0x0102);
+        assert_eq!(Endian::Big.read(&[0xff; 8]), u64::MAX);
+        assert_eq!(Endian::from_name("BE"), Some(Endian::Big));
+        assert_eq!(Endian::from_name("middle"), None);
+    }
+
+    #[test]
+    fn test_typed_column_after_bytes() {
+        let data: Vec<u8> = [1u16, 2, 65535, 300].iter().flat_map(|v| v.to_le_bytes()).collect();
+        let out = capture(&data, HexOptions { typed: Some(DataType::U16), ..HexOptions::new(8) });
+        assert_eq!(
+            out,
+            "00000000: 01 00 02 00 ff ff 2c 01      1     2 65535   300  |......,. |\n"
+        );
+    }
+
+    #[test]
+    fn test_typed_only_replaces_bytes() {
+        let data: Vec<u8> = [1.5f32, -2.0].iter().flat_map(|v| v.to_le_bytes()).collect();
+        let out = capture(&data, HexOptions { typed: Some(DataType::F32), typed_only: true, ..HexOptions::new(8) });
+        assert_eq!(out, "00000000:             1.5              -2  |...?.... |\n");
+    }
+
+    #[test]
+    fn test_typed_partial_element_keeps_alignment() {
+        let options = HexOptions { typed: Some(DataType::U32), typed_only: true, ..HexOptions::new(8) };
+        let out = capture(b"ABCDEFGHIJ", options);
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines[1], "00000008:          -             |IJ       |");
+        assert_eq!(lines[0].find('|'), lines[1].find('|'));
+    }
+
+    #[test]
+    fn test_byte_class_colors() {
+        with_color_override(true, || {
+            let mut buf = Vec::new();
+            let palette = Palette { null: Color::Blue, ff: Color::Red, ..Palette::default() };
+            let theme = Theme { offset: Color::Green, palette, ..Theme::default() };
+            let options = HexOptions { color: true, theme, ..HexOptions::new(4) };
+            let mut writer = HexWriter::new(&mut buf, options, 0);
+            writer.write(&[0x00, b'A', 0xff]).unwrap();
+            writer.finish().unwrap();
+            let out = String::from_utf8(buf).unwrap();
+            assert!(out.contains(&"00".color(Color::Blue).to_string()), "{out:?}");
+            assert!(out.contains(&"ff".color(Color::Red).to_string()), "{out:?}");
+            assert!(out.contains(&"41".color(palette.printable).to_string()), "{out:?}");
+            assert!(out.contains(&"A".color(palette.printable).to_string()), "{out:?}");
+            assert!(out.contains(&".".color(Color::Red).to_string()), "glyph shares the cell color: {out:?}");
+            assert!(out.starts_with(&"00000000".color(Color::Green).to_string()), "{out:?}");
+        });
+    }
+
+    #[test]
+    fn test_byte_class_colors_keep_alignment() {
+        let data: Vec<u8> = (0..=255u8).collect();
+        let out = with_color_override(true, || capture_hex(&data[..20], 16, true, Encoding::Ascii));
+        assert!(out.contains("\x1b["), "{out:?}");
+        let plain = capture_hex(&data[..20], 16, false, Encoding::Ascii);
+        let strip = |s: &str| {
+            let mut text = String::new();
+            let mut escape = false;
+            for c in s.chars() {
+                match c {
+                    '\x1b' => escape = true,
+                    'm' if escape => escape = false,
+                    _ if !escape => text.push(c),
+                    _ => {}
+                }
+            }
+            text
+        };
+        assert_eq!(strip(&out), plain);
+    }
+
+    #[test]
+    fn test_utf8_wide_and_combining_alignment() {
+        let cases: [&[u8]; 4] = [
+            b"plain ascii text",
+            "世界世界".as_bytes(),
+            "🎉 party".as_bytes(),
+            "e\u{301}e\u{301}e\u{301}".as_bytes(),
+        ];
+        let bars: Vec<usize> = cases
+            .iter()
+            .map(|data| {
+                let out = capture_hex(data, 16, false, Encoding::Utf8);
+                let line = out.lines().next().unwrap();
+                let (_, chars) = line.split_once('|').unwrap();
+                chars.chars().map(|c| c.width().unwrap_or(0)).sum()
+            })
+            .collect();
+        assert!(bars.iter().all(|&cells| cells == bars[0]), "{:?}", bars);
+    }
+
+    #[test]
+    fn test_utf8_row_boundary_uses_placeholders() {
+        // 世 starts at byte 7 and ends at byte 9 of a 8-byte-wide dump
+        let out = capture_hex("abcdefg世!".as_bytes(), 8, false, Encoding::Utf8);
+        let lines: Vec<&str> = out.lines().collect();
+        assert!(lines[0].ends_with("|abcdefg· |"), "{out}");
+        assert!(lines[1].ends_with("|··!      |"), "{out}");
+        assert!(!out.contains('\u{fffd}'));
+    }
+
+    #[test]
+    fn test_utf16_column_spans_both_bytes() {
+        let data: Vec<u8> = "Hi🎉!".encode_utf16().flat_map(u16::to_le_bytes).collect();
+        let mut buf = Vec::new();
+        let options = HexOptions { encoding: Encoding::Utf16Le, ..HexOptions::new(8) };
+        let mut writer = HexWriter::new(&mut buf, options, 0);
+        writer.write(&data).unwrap();
+        writer.finish().unwrap();
+        let out = String::from_utf8(buf).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert!(lines[0].ends_with("|H i 🎉   |"), "{out}");
+        assert!(lines[1].ends_with("|!        |"), "{out}");
+    }
+
+    #[test]
+    fn test_highlight_brackets_without_color() {
+        let highlights = vec![Highlight::from_spec("5:4").unwrap(), Highlight::from_spec("0xd:3").unwrap()];
+        let out = capture(b"Hello, World! 01", HexOptions { highlights, ..HexOptions::new(16) });
+        assert_eq!(
+            out,
+            "00000000: 48 65 6c 6c 6f[2c 20 57][6f]72 6c 64 21[20 30 31] |Hello[, Wo]rld![ 01] |\n"
+        );
+
+        // Brackets close before blank cells on a short row, and take the
+        // character column's padding
+        let highlights = vec![Highlight::from_spec("1:8").unwrap()];
+        let out = capture(b"abc", HexOptions { highlights, ..HexOptions::new(8) });
+        assert_eq!(out, "00000000: 61[62 63]                |a[bc]    |\n");
+
+        let highlights = vec![Highlight::from_spec("3:1").unwrap()];
+        let out = capture(b"abcdefgh", HexOptions { word_size: 2, highlights, ..HexOptions::new(8) });
+        assert_eq!(out, "00000000: 6261[6463]6665 6867  |abc[d]efgh |\n");
+    }
+
+    #[test]
+    fn test_highlight_colors() {
+        with_color_override(true, || {
+            let theme = Theme { highlight: Color::Blue, ..Theme::default() };
+            let highlights = vec![Highlight::from_spec("1:1").unwrap(), Highlight::from_spec("4:1:red").unwrap()];
+            let options = HexOptions { color: true, theme, encoding: Encoding::Utf8, highlights, ..HexOptions::new(8) };
+            let out = capture("ab世!".as_bytes(), options);
+            assert!(out.contains(&"62".black().on_color(Color::Blue).to_string()), "{out:?}");
+            assert!(out.contains(&"b".black().on_color(Color::Blue).to_string()), "{out:?}");
+            assert!(out.contains(&"96".black().on_color(Color::Red).to_string()), "{out:?}");
+            // A glyph is highlighted when any of its bytes are
+            assert!(out.contains(&"世".black().on_color(Color::Red).to_string()), "{out:?}");
+            assert!(out.contains(&"!".color(theme.palette.printable).to_string()), "{out:?}");
+            assert!(!out.contains(']'), "{out:?}");
+        });
+    }
+
+    #[test]
+    fn test_highlight_is_never_squeezed() {
+        let highlights = vec![Highlight::from_spec("0x11:1").unwrap()];
+        let out = capture(&[0u8; 24], HexOptions { squeeze: true, highlights, ..HexOptions::new(4) });
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 5, "{out}");
+        assert_eq!(lines[1], "*");
+        assert!(lines[2].starts_with("00000010: 00[00]00 00"), "{out}");
+        assert_eq!(lines[3], "*");
+    }
+
+    #[test]
+    fn test_xxd_style_matches_xxd() {
+        let data = b"Hello, World!\nabcdefghijklmnopqrstuvwxyz\x00\x01\xff";
+        let mut buf = Vec::new();
+        // Squeezing never applies to xxd rows
+        let options = HexOptions { style: Style::Xxd, squeeze: true, color: true, ..HexOptions::new(16) };
+        let mut writer = HexWriter::new(&mut buf, options, 0);
+        writer.write(data).unwrap();
+        writer.finish().unwrap();
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "\
+00000000: 4865 6c6c 6f2c 2057 6f72 6c64 210a 6162  Hello, World!.ab
+00000010: 6364 6566 6768 696a 6b6c 6d6e 6f70 7172  cdefghijklmnopqr
+00000020: 7374 7576 7778 797a 0001 ff              stuvwxyz...
+"
+        );
+
+        let mut buf = Vec::new();
+        let mut writer = HexWriter::new(&mut buf, HexOptions { style: Style::Xxd, ..HexOptions::new(5) }, 0);
+        writer.write(&data[..7]).unwrap();
+        writer.finish().unwrap();
+        assert_eq!(String::from_utf8(buf).unwrap(), "00000000: 4865 6c6c 6f  Hello\n00000005: 2c20          , \n");
+
+        // Past 4 GiB the offset grows at its natural width, as in xxd
+        let mut buf = Vec::new();
+        let options = HexOptions { style: Style::Xxd, ..HexOptions::new(4) };
+        let mut writer = HexWriter::new(&mut buf, options, 0xffff_fffc);
+        writer.set_end_offset(0x1_0000_0004);
+        writer.write(b"abcdefgh").unwrap();
+        writer.finish().unwrap();
+        assert_eq!(String::from_utf8(buf).unwrap(), "fffffffc: 6162 6364  abcd\n100000000: 6566 6768  efgh\n");
+    }
+
+    #[test]
+    fn test_endian_write_inverts_read() {
+        for endian in [Endian::Little, Endian::Big] {
+            let bytes = [0x12, 0x34, 0x56];
+            assert_eq!(endian.write(endian.read(&bytes), 3), bytes);
+        }
+        assert_eq!(Endian::Big.write(0x0102, 2), [0x01, 0x02]);
+    }
+
+    #[test]
+    fn test_json_rows() {
+        let options = HexOptions { style: Style::Json, squeeze: true, ..HexOptions::new(4) };
+        let mut buf = Vec::new();
+        let mut writer = HexWriter::new(&mut buf, options, 0x10);
+        writer.write(b"\0\0\0\0\0\0\0\0Hi\n").unwrap();
+        writer.finish().unwrap();
+        let out = String::from_utf8(buf).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        // Never squeezed, so every row is a record
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], r#"{"type":"row","offset":16,"bytes":[0,0,0,0],"text":"....","values":null}"#);
+        assert_eq!(lines[2], r#"{"type":"row","offset":24,"bytes":[72,105,10],"text":"Hi.","values":null}"#);
+    }
+
+    #[test]
+    fn test_json_rows_with_typed_values() {
+        let options = HexOptions { style: Style::Json, typed: Some(DataType::U16), ..HexOptions::new(4) };
+        let mut buf = Vec::new();
+        let mut writer = HexWriter::new(&mut buf, options, 0);
+        writer.write(&[1, 0, 0, 1, 0xff]).unwrap();
+        writer.finish().unwrap();
+        let out = String::from_utf8(buf).unwrap();
+        let rows: Vec<serde_json::Value> = out.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(rows[0]["values"], json!(["1", "256"]));
+        // A partial trailing element has no value
+        assert_eq!(rows[1]["values"], json!([]));
+    }
+
+    #[test]
+    fn test_html_rows() {
+        let options = HexOptions { style: Style::Html, squeeze: true, ..HexOptions::new(2) };
+        let mut buf = Vec::new();
+        let mut writer = HexWriter::new(&mut buf, options, 0);
+        writer.write(b"\0\0\0\0\0\0<").unwrap();
+        writer.finish().unwrap();
+        let out = String::from_utf8(buf).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("<span class=\"offset\">00000000</span>: <span class=\"null\" title="));
+        assert_eq!(lines[1], "<span class=\"separator\">*</span>");
+        assert!(lines[2].contains("title=\"offset 0x6 (6), value 60\">&lt;</span>"));
+        assert!(lines[2].ends_with("<span class=\"separator\">|</span>"));
+    }
 }
@@ -0,0 +1,97 @@
+//! Byte ranges picked out with `--highlight`
+
+use colored::Color;
+
+use super::palette::parse_color;
+use crate::utils::parsing::parse_offset;
+
+/// A run of bytes drawn on a background color
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Highlight {
+    /// Absolute offset of the first byte
+    pub start: u64,
+    /// Number of bytes, at least 1
+    pub len: u64,
+    /// Background color, or the theme's highlight color if unset
+    pub color: Option<Color>,
+}
+
+impl Highlight {
+    /// Parse `START:LEN[:COLOR]`, where START and LEN are decimal or `0x` hex
+    /// and COLOR is a name or `#rrggbb` (e.g. `0x1c:4:red`)
+    pub fn from_spec(spec: &str) -> Result<Self, String> {
+        let mut parts = spec.splitn(3, ':');
+        let (Some(start), Some(len)) = (parts.next(), parts.next()) else {
+            return Err(format!("invalid highlight '{}', expected START:LEN[:COLOR]", spec));
+        };
+        let start = parse_offset(start)
+            .and_then(|start| u64::try_from(start).ok())
+            .ok_or_else(|| format!("invalid highlight start '{}'", start))?;
+        let len = parse_offset(len)
+            .and_then(|len| u64::try_from(len).ok())
+            .filter(|&len| len > 0)
+            .ok_or_else(|| format!("invalid highlight length '{}', expected a positive number", len))?;
+        let color = match parts.next() {
+            Some(name) => Some(parse_color(name.trim()).ok_or_else(|| format!("unknown color '{}'", name))?),
+            None => None,
+        };
+        Ok(Highlight { start, len, color })
+    }
+
+    /// Whether the byte at `offset` falls inside this range
+    pub fn contains(&self, offset: u64) -> bool {
+        offset >= self.start && offset - self.start < self.len
+    }
+}
+
+/// Background for the byte at `offset`: the last matching highlight wins,
+/// falling back to `default` when that highlight names no color
+pub fn highlight_at(highlights: &[Highlight], offset: u64, default: Color) -> Option<Color> {
+    highlights.iter().rev().find(|h| h.contains(offset)).map(|h| h.color.unwrap_or(default))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_spec() {
+        assert_eq!(
+            Highlight::from_spec("0x1c:4").unwrap(),
+            Highlight { start: 0x1c, len: 4, color: None }
+        );
+        assert_eq!(
+            Highlight::from_spec("16:0x10:bright-red").unwrap(),
+            Highlight { start: 16, len: 16, color: Some(Color::BrightRed) }
+        );
+        assert_eq!(
+            Highlight::from_spec("0:1:#ff8800").unwrap().color,
+            Some(Color::TrueColor { r: 0xff, g: 0x88, b: 0x00 })
+        );
+    }
+
+    #[test]
+    fn test_from_spec_errors() {
+        assert!(Highlight::from_spec("16").is_err());
+        assert!(Highlight::from_spec("x:4").is_err());
+        assert!(Highlight::from_spec("-4:4").is_err());
+        assert!(Highlight::from_spec("0:0").is_err());
+        assert!(Highlight::from_spec("0:4:mauve").is_err());
+    }
+
+    #[test]
+    fn test_highlight_at() {
+        let highlights = [
+            Highlight { start: 4, len: 4, color: None },
+            Highlight { start: 6, len: 4, color: Some(Color::Red) },
+        ];
+        assert_eq!(highlight_at(&highlights, 3, Color::Yellow), None);
+        assert_eq!(highlight_at(&highlights, 4, Color::Yellow), Some(Color::Yellow));
+        assert_eq!(highlight_at(&highlights, 7, Color::Yellow), Some(Color::Red));
+        assert_eq!(highlight_at(&highlights, 9, Color::Yellow), Some(Color::Red));
+        assert_eq!(highlight_at(&highlights, 10, Color::Yellow), None);
+
+        let end = Highlight { start: u64::MAX, len: 1, color: None };
+        assert!(end.contains(u64::MAX));
+    }
+}
@@ -0,0 +1,243 @@
+//! Standalone HTML reports (`--format html`)
+//!
+//! A report is one self-contained file: [`write_html_head`] inlines the
+//! stylesheet, each input becomes a section with an optional metadata table
+//! and minimap, and the hex rows come from a [`HexWriter`](super::HexWriter)
+//! in [`Style::Html`](super::Style::Html) inside a `<pre>`.
+
+use colored::Color;
+use image::{ImageFormat, RgbImage};
+use std::io::{self, Cursor, Write};
+
+use super::metadata::Metadata;
+use super::palette::ByteClass;
+use super::theme::Theme;
+use crate::utils::color::color_to_rgb;
+
+/// Width and height of one minimap cell in CSS pixels, the shape of a
+/// terminal character cell
+const MINIMAP_CELL: (usize, usize) = (8, 16);
+
+/// Start the document: doctype, `title` and a stylesheet with `theme`'s colors
+///
+/// The page is dark unless the theme's printable-byte color is itself dark,
+/// as in the `light` theme.
+pub fn write_html_head<W: Write>(out: &mut W, title: &str, theme: &Theme) -> io::Result<()> {
+    let (r, g, b) = color_to_rgb(theme.palette.printable);
+    let luma = 0.299 * f32::from(r) + 0.587 * f32::from(g) + 0.114 * f32::from(b);
+    let light = luma < 128.0;
+    let (background, foreground) = if light { ("#ffffff", "#1c1c1c") } else { ("#1c1c1c", "#d0d0d0") };
+
+    writeln!(out, "<!DOCTYPE html>")?;
+    writeln!(out, "<html lang=\"en\">")?;
+    writeln!(out, "<head>")?;
+    writeln!(out, "<meta charset=\"utf-8\">")?;
+    writeln!(out, "<title>{}</title>", escape(title))?;
+    writeln!(out, "<style>")?;
+    writeln!(out, "body {{ background: {}; color: {}; font-family: sans-serif; }}", background, foreground)?;
+    writeln!(out, "pre.hex {{ font-family: monospace; line-height: 1.25; }}")?;
+    writeln!(out, "pre.hex span[title]:hover {{ outline: 1px solid currentColor; }}")?;
+    writeln!(out, "table.meta {{ border-collapse: collapse; font-family: monospace; }}")?;
+    writeln!(out, "table.meta th {{ text-align: left; padding-right: 1em; font-weight: normal; }}")?;
+    writeln!(out, "table.meta tr.field th {{ padding-left: 2ch; }}")?;
+    writeln!(out, "img.minimap {{ image-rendering: pixelated; display: block; margin: 1em 0; }}")?;
+    writeln!(out, ".offset {{ color: {}; }}", css(theme.offset))?;
+    writeln!(out, ".separator {{ color: {}; }}", css(theme.separator))?;
+    writeln!(out, ".label {{ color: {}; }}", css(theme.label))?;
+    for class in ByteClass::ALL {
+        writeln!(out, ".{} {{ color: {}; }}", class.name(), css(theme.palette.color(class)))?;
+    }
+    writeln!(out, "</style>")?;
+    writeln!(out, "</head>")?;
+    writeln!(out, "<body>")
+}
+
+/// Close the document opened by [`write_html_head`]
+pub fn write_html_tail<W: Write>(out: &mut W) -> io::Result<()> {
+    writeln!(out, "</body>")?;
+    writeln!(out, "</html>")
+}
+
+/// Open the section for one input, headed by its name
+pub fn write_html_section_start<W: Write>(out: &mut W, name: &str) -> io::Result<()> {
+    writeln!(out, "<section>")?;
+    writeln!(out, "<h2>{}</h2>", escape(name))
+}
+
+/// Close the section opened by [`write_html_section_start`]
+pub fn write_html_section_end<W: Write>(out: &mut W) -> io::Result<()> {
+    writeln!(out, "</section>")
+}
+
+/// `--meta` as a two-column table; format fields are indented under `Format`
+pub fn write_html_metadata<W: Write>(out: &mut W, metadata: &Metadata) -> io::Result<()> {
+    writeln!(out, "<table class=\"meta\">")?;
+    for (label, value) in metadata.entries() {
+        writeln!(out, "<tr><th class=\"label\">{}</th><td>{}</td></tr>", escape(label), escape(&value))?;
+    }
+    for field in &metadata.fields {
+        writeln!(
+            out,
+            "<tr class=\"field\"><th class=\"label\">{}</th><td>{}</td></tr>",
+            escape(field.label),
+            escape(&field.value.to_string())
+        )?;
+    }
+    writeln!(out, "</table>")
+}
+
+/// The minimap as an inline PNG, one pixel per cell, scaled up to the size
+/// it takes in a terminal and drawn on the theme's minimap background
+pub fn write_html_minimap<W: Write>(out: &mut W, pixels: &RgbImage, theme: &Theme) -> io::Result<()> {
+    let mut png = Cursor::new(Vec::new());
+    pixels.write_to(&mut png, ImageFormat::Png).map_err(io::Error::other)?;
+    let background = match theme.minimap_background {
+        Some(color) => format!(" background: {}; padding: 4px;", css(color)),
+        None => String::new(),
+    };
+    let style = format!(
+        "width: {}px; height: {}px;{}",
+        pixels.width() as usize * MINIMAP_CELL.0,
+        pixels.height() as usize * MINIMAP_CELL.1,
+        background
+    );
+    writeln!(
+        out,
+        "<img class=\"minimap\" alt=\"minimap\" style=\"{}\" src=\"data:image/png;base64,{}\">",
+        style,
+        base64(png.get_ref())
+    )
+}
+
+/// `text` in a span of `class`, for offsets and separators
+pub(crate) fn span(class: &str, text: &str) -> String {
+    format!("<span class=\"{}\">{}</span>", class, escape(text))
+}
+
+/// A byte cell or glyph: colored by `class` (left plain if its bytes are
+/// mixed), black on `background` when highlighted, and titled with its
+/// absolute offset and decimal `value` so hovering shows them
+pub(crate) fn cell(
+    text: &str,
+    class: Option<ByteClass>,
+    background: Option<Color>,
+    offset: u64,
+    value: &str,
+) -> String {
+    let class = class.map_or(String::new(), |class| format!(" class=\"{}\"", class.name()));
+    let style = background.map_or(String::new(), |color| {
+        format!(" style=\"background: {}; color: #000000\"", css(color))
+    });
+    format!(
+        "<span{}{} title=\"offset 0x{:x} ({}), value {}\">{}</span>",
+        class,
+        style,
+        offset,
+        offset,
+        escape(value),
+        escape(text)
+    )
+}
+
+/// `#rrggbb` for a terminal color
+fn css(color: Color) -> String {
+    let (r, g, b) = color_to_rgb(color);
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+/// Escape text for use in element content and quoted attributes
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Standard, padded base64 for `data:` URLs
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (i, &b)| bits | u32::from(b) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(bits >> (18 - 6 * i)) as usize & 0x3f] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+        assert_eq!(base64(&[0xff, 0xfe, 0x00]), "//4A");
+    }
+
+    #[test]
+    fn test_cell() {
+        assert_eq!(
+            cell("<", Some(ByteClass::Printable), None, 0x1c, "60"),
+            "<span class=\"printable\" title=\"offset 0x1c (28), value 60\">&lt;</span>"
+        );
+        let highlighted = cell("ff", None, Some(Color::Yellow), 0, "255");
+        assert_eq!(
+            highlighted,
+            "<span style=\"background: #cdcd00; color: #000000\" title=\"offset 0x0 (0), value 255\">ff</span>"
+        );
+    }
+
+    #[test]
+    fn test_head_uses_theme_colors() {
+        let mut buf = Vec::new();
+        write_html_head(&mut buf, "a & b", &Theme::default()).unwrap();
+        let head = String::from_utf8(buf).unwrap();
+        assert!(head.contains("<title>a &amp; b</title>"));
+        assert!(head.contains(".offset { color: #00cdcd; }"));
+        assert!(head.contains(".ff { color: #cd0000; }"));
+        assert!(head.contains("background: #1c1c1c"));
+
+        let mut buf = Vec::new();
+        write_html_head(&mut buf, "", &Theme::builtin("light").unwrap()).unwrap();
+        assert!(String::from_utf8(buf).unwrap().contains("background: #ffffff"));
+    }
+
+    #[test]
+    fn test_metadata_table() {
+        let metadata = Metadata::of_stream("<in>".to_string(), None, None, b"BZh9");
+        let mut buf = Vec::new();
+        write_html_metadata(&mut buf, &metadata).unwrap();
+        let table = String::from_utf8(buf).unwrap();
+        assert!(table.contains("<tr><th class=\"label\">File</th><td>&lt;in&gt;</td></tr>"));
+        assert!(table.contains("<tr><th class=\"label\">Format</th><td>BZIP2</td></tr>"));
+        assert!(table.contains("<tr class=\"field\"><th class=\"label\">Block size</th><td>900000 bytes</td></tr>"));
+    }
+
+    #[test]
+    fn test_minimap_is_inline_png() {
+        let pixels = RgbImage::from_pixel(3, 2, image::Rgb([255, 0, 0]));
+        let mut buf = Vec::new();
+        write_html_minimap(&mut buf, &pixels, &Theme::default()).unwrap();
+        let img = String::from_utf8(buf).unwrap();
+        assert!(img.contains("style=\"width: 24px; height: 32px;\""));
+        // The PNG signature, base64 encoded
+        assert!(img.contains("src=\"data:image/png;base64,iVBORw0KGgo"));
+    }
+}
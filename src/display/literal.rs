@@ -0,0 +1,300 @@
+//! Source-code literals for embedding a dumped range (`--export`)
+
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Language to write the literal in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    /// `unsigned char name[]` plus `unsigned int name_len`, as `xxd -i` writes
+    C,
+    /// `const NAME: [u8; N]`
+    Rust,
+    /// A parenthesised run of `b"..."` literals
+    Python,
+    /// `var name = []byte{...}`
+    Go,
+}
+
+impl Language {
+    /// Parse a language name: `c`, `rust`, `python` or `go` (or `rs`, `py`)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "c" => Some(Language::C),
+            "rust" | "rs" => Some(Language::Rust),
+            "python" | "py" => Some(Language::Python),
+            "go" | "golang" => Some(Language::Go),
+            _ => None,
+        }
+    }
+}
+
+/// Identifier for the literal, derived from the input's file name
+///
+/// Runs of characters that cannot appear in an identifier become word
+/// breaks, joined in each language's usual style: `firmware-v2.bin` gives
+/// `firmware_v2_bin` (C, Python), `FIRMWARE_V2_BIN` (Rust) or
+/// `firmwareV2Bin` (Go). A name that is a keyword, like a file called
+/// `int`, gets a trailing `_`.
+pub fn identifier(name: &str, language: Language) -> String {
+    let file_name = Path::new(name).file_name().map_or_else(|| name.into(), |n| n.to_string_lossy());
+    let mut words: Vec<&str> =
+        file_name.split(|c: char| !c.is_ascii_alphanumeric()).filter(|w| !w.is_empty()).collect();
+    if words.is_empty() {
+        words.push("data");
+    }
+    let ident = match language {
+        Language::C => words.join("_"),
+        Language::Rust => words.join("_").to_ascii_uppercase(),
+        Language::Python => words.join("_").to_ascii_lowercase(),
+        Language::Go => {
+            let mut ident = words[0].to_ascii_lowercase();
+            for word in &words[1..] {
+                let (first, rest) = word.split_at(1);
+                ident.push_str(&first.to_ascii_uppercase());
+                ident.push_str(&rest.to_ascii_lowercase());
+            }
+            ident
+        }
+    };
+    if ident.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{}", ident)
+    } else if keywords(language).contains(&ident.as_str()) {
+        ident + "_"
+    } else {
+        ident
+    }
+}
+
+/// Reserved words an identifier must not be; Rust's are all lowercase, so
+/// its upper-case constants never collide
+fn keywords(language: Language) -> &'static [&'static str] {
+    match language {
+        Language::C => &[
+            "alignas", "alignof", "auto", "bool", "break", "case", "char", "const", "constexpr", "continue",
+            "default", "do", "double", "else", "enum", "extern", "false", "float", "for", "goto", "if", "inline",
+            "int", "long", "nullptr", "register", "restrict", "return", "short", "signed", "sizeof", "static",
+            "static_assert", "struct", "switch", "thread_local", "true", "typedef", "typeof", "typeof_unqual",
+            "union", "unsigned", "void", "volatile", "while",
+        ],
+        Language::Rust => &[],
+        Language::Python => &[
+            "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del", "elif", "else",
+            "except", "finally", "for", "from", "global", "if", "import", "in", "is", "lambda", "nonlocal", "not",
+            "or", "pass", "raise", "return", "try", "while", "with", "yield",
+        ],
+        // `byte` is only predeclared, but shadowing it breaks the `[]byte` type
+        Language::Go => &[
+            "break", "byte", "case", "chan", "const", "continue", "default", "defer", "else", "fallthrough", "for",
+            "func", "go", "goto", "if", "import", "interface", "map", "package", "range", "return", "select",
+            "struct", "switch", "type", "var",
+        ],
+    }
+}
+
+/// Write `data` as a source literal named `name`, `width` bytes per line
+///
+/// The sibling of [`write_hex`](super::write_hex) for embedding firmware
+/// snippets and test vectors in code.
+pub fn write_literal<W: Write>(
+    out: &mut W,
+    data: &[u8],
+    language: Language,
+    name: &str,
+    width: usize,
+) -> io::Result<()> {
+    let mut writer = LiteralWriter::new(out, language, name, width, data.len() as u64)?;
+    writer.write(data)?;
+    writer.finish()?;
+    Ok(())
+}
+
+/// Incremental source literal writer
+///
+/// The literal's length is declared up front, as Rust's array type and C's
+/// last-line comma need it, so input of a known size is written line by
+/// line like [`HexWriter`](super::HexWriter) rows instead of collected.
+pub struct LiteralWriter<W: Write> {
+    out: W,
+    language: Language,
+    name: String,
+    width: usize,
+    // Bytes promised and bytes written so far, not counting `row`
+    len: u64,
+    written: u64,
+    row: Vec<u8>,
+}
+
+impl<W: Write> LiteralWriter<W> {
+    /// Open a literal of `len` bytes named `name`, `width` bytes per line
+    pub fn new(mut out: W, language: Language, name: &str, width: usize, len: u64) -> io::Result<Self> {
+        match language {
+            Language::C => writeln!(out, "unsigned char {}[] = {{", name)?,
+            Language::Rust => writeln!(out, "const {}: [u8; {}] = [", name, len)?,
+            Language::Go => writeln!(out, "var {} = []byte{{", name)?,
+            // Adjacent bytes literals concatenate; an empty group would be a tuple
+            Language::Python if len == 0 => writeln!(out, "{} = b\"\"", name)?,
+            Language::Python => writeln!(out, "{} = (", name)?,
+        }
+        let width = width.max(1);
+        Ok(LiteralWriter {
+            out,
+            language,
+            name: name.to_string(),
+            width,
+            len,
+            written: 0,
+            row: Vec::with_capacity(width),
+        })
+    }
+
+    /// Feed more of the literal, writing every line that is now complete
+    pub fn write(&mut self, mut data: &[u8]) -> io::Result<()> {
+        while !data.is_empty() {
+            let take = (self.width - self.row.len()).min(data.len());
+            self.row.extend_from_slice(&data[..take]);
+            data = &data[take..];
+            if self.row.len() == self.width {
+                self.write_row()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the last partial line and close the literal, returning the
+    /// inner writer
+    ///
+    /// Fails if the input did not hold exactly the `len` bytes declared.
+    pub fn finish(mut self) -> io::Result<W> {
+        if !self.row.is_empty() {
+            self.write_row()?;
+        }
+        if self.written != self.len {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("input changed size: expected {} bytes, read {}", self.len, self.written),
+            ));
+        }
+        match self.language {
+            Language::C => {
+                writeln!(self.out, "}};")?;
+                writeln!(self.out, "unsigned int {}_len = {};", self.name, self.len)?;
+            }
+            Language::Rust => writeln!(self.out, "];")?,
+            Language::Go => writeln!(self.out, "}}")?,
+            Language::Python if self.len == 0 => {}
+            Language::Python => writeln!(self.out, ")")?,
+        }
+        Ok(self.out)
+    }
+
+    /// One line: comma-separated `0x..` bytes, or a Python `b"..."` run
+    fn write_row(&mut self) -> io::Result<()> {
+        self.written += self.row.len() as u64;
+        let (indent, trailing_comma) = match self.language {
+            Language::C => ("  ", false),
+            Language::Rust => ("    ", true),
+            Language::Go => ("\t", true),
+            Language::Python => {
+                let escaped: String = self.row.iter().map(|b| format!("\\x{:02x}", b)).collect();
+                self.row.clear();
+                return writeln!(self.out, "    b\"{}\"", escaped);
+            }
+        };
+        let cells: Vec<String> = self.row.iter().map(|b| format!("0x{:02x}", b)).collect();
+        let comma = if trailing_comma || self.written < self.len { "," } else { "" };
+        self.row.clear();
+        writeln!(self.out, "{}{}{}", indent, cells.join(", "), comma)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn literal(data: &[u8], language: Language, width: usize) -> String {
+        let mut buf = Vec::new();
+        write_literal(&mut buf, data, language, "blob", width).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn test_from_name() {
+        assert_eq!(Language::from_name("C"), Some(Language::C));
+        assert_eq!(Language::from_name("rs"), Some(Language::Rust));
+        assert_eq!(Language::from_name("python"), Some(Language::Python));
+        assert_eq!(Language::from_name("golang"), Some(Language::Go));
+        assert_eq!(Language::from_name("java"), None);
+    }
+
+    #[test]
+    fn test_identifier() {
+        let name = "/tmp/firmware-v2.bin";
+        assert_eq!(identifier(name, Language::C), "firmware_v2_bin");
+        assert_eq!(identifier(name, Language::Rust), "FIRMWARE_V2_BIN");
+        assert_eq!(identifier(name, Language::Python), "firmware_v2_bin");
+        assert_eq!(identifier(name, Language::Go), "firmwareV2Bin");
+        assert_eq!(identifier("2024 Test Vector.dat", Language::C), "_2024_Test_Vector_dat");
+        assert_eq!(identifier("<stdin>", Language::Rust), "STDIN");
+        assert_eq!(identifier("...", Language::Go), "data");
+        assert_eq!(identifier("int", Language::C), "int_");
+        assert_eq!(identifier("data/class", Language::Python), "class_");
+        assert_eq!(identifier("type", Language::Go), "type_");
+        assert_eq!(identifier("type", Language::Rust), "TYPE");
+    }
+
+    #[test]
+    fn test_c() {
+        assert_eq!(
+            literal(b"Hello", Language::C, 2),
+            "unsigned char blob[] = {\n  0x48, 0x65,\n  0x6c, 0x6c,\n  0x6f\n};\nunsigned int blob_len = 5;\n"
+        );
+    }
+
+    #[test]
+    fn test_rust() {
+        assert_eq!(
+            literal(b"Hello", Language::Rust, 3),
+            "const blob: [u8; 5] = [\n    0x48, 0x65, 0x6c,\n    0x6c, 0x6f,\n];\n"
+        );
+    }
+
+    #[test]
+    fn test_python() {
+        assert_eq!(literal(b"Hi\x00", Language::Python, 2), "blob = (\n    b\"\\x48\\x69\"\n    b\"\\x00\"\n)\n");
+        assert_eq!(literal(b"", Language::Python, 2), "blob = b\"\"\n");
+    }
+
+    #[test]
+    fn test_go() {
+        assert_eq!(literal(b"\xff\x00", Language::Go, 16), "var blob = []byte{\n\t0xff, 0x00,\n}\n");
+    }
+
+    #[test]
+    fn test_writer_matches_whole_literal() {
+        let data: Vec<u8> = (0..23).collect();
+        for language in [Language::C, Language::Rust, Language::Python, Language::Go] {
+            let mut buf = Vec::new();
+            let mut writer = LiteralWriter::new(&mut buf, language, "blob", 8, data.len() as u64).unwrap();
+            for chunk in data.chunks(5) {
+                writer.write(chunk).unwrap();
+            }
+            writer.finish().unwrap();
+            assert_eq!(String::from_utf8(buf).unwrap(), literal(&data, language, 8));
+        }
+    }
+
+    #[test]
+    fn test_writer_rejects_short_input() {
+        let mut buf = Vec::new();
+        let mut writer = LiteralWriter::new(&mut buf, Language::Rust, "blob", 8, 4).unwrap();
+        writer.write(b"abc").unwrap();
+        assert_eq!(writer.finish().err().map(|e| e.kind()), Some(io::ErrorKind::UnexpectedEof));
+    }
+
+    #[test]
+    fn test_empty() {
+        assert_eq!(literal(b"", Language::C, 8), "unsigned char blob[] = {\n};\nunsigned int blob_len = 0;\n");
+        assert_eq!(literal(b"", Language::Rust, 8), "const blob: [u8; 0] = [\n];\n");
+    }
+}
@@ -2,19 +2,34 @@
 
 use std::fs;
 use std::io;
-use std::io::Read;
 use std::path::Path;
 use std::time::{SystemTime, UNIX_EPOCH};
+use colored::Colorize;
+use serde_json::json;
+use crate::display::theme::Theme;
 use crate::formats::detection::detect_file_format;
-use crate::formats::metadata::extract_format_metadata;
+use crate::formats::metadata::{format_fields, FormatField};
+use crate::io::device::{input_len, sector_size, FileKind, SectorReader};
+use crate::io::file::read_full;
 
-fn format_system_time(t: io::Result<SystemTime>) -> String {
+/// Number of leading bytes inspected for format detection
+pub const FORMAT_PREFIX_LEN: usize = 1024;
+
+/// Seconds since the Unix epoch, negative before it; `None` if the platform
+/// does not record the timestamp
+fn unix_seconds(t: io::Result<SystemTime>) -> Option<i64> {
+    let t = t.ok()?;
+    match t.duration_since(UNIX_EPOCH) {
+        Ok(d) => i64::try_from(d.as_secs()).ok(),
+        Err(e) => i64::try_from(e.duration().as_secs()).ok().map(|secs| -secs),
+    }
+}
+
+fn format_timestamp(t: Option<i64>) -> String {
     match t {
-        Ok(ts) => match ts.duration_since(UNIX_EPOCH) {
-            Ok(d) => format!("{} (unix)", d.as_secs()),
-            Err(_) => "<pre-epoch>".to_string(),
-        },
-        Err(_) => "<unavailable>".to_string(),
+        Some(secs) if secs >= 0 => format!("{} (unix)", secs),
+        Some(_) => "<pre-epoch>".to_string(),
+        None => "<unavailable>".to_string(),
     }
 }
 
@@ -29,42 +44,169 @@ fn format_permissions(perm: &fs::Permissions) -> String {
     if perm.readonly() { "read-only".to_string() } else { "read-write".to_string() }
 }
 
+/// What `--meta` reports about one input
+#[derive(Debug, Clone)]
+pub struct Metadata {
+    /// Path or display name of the input
+    pub file: String,
+    /// Kind of file; `None` for standard input
+    pub kind: Option<FileKind>,
+    /// Size, timestamps and permissions, for inputs that are opened by path
+    pub file_info: Option<FileInfo>,
+    /// Name of the compressor, when `--decompress` is showing the payload
+    pub compression: Option<String>,
+    /// Detected format, e.g. `PNG`; `None` if unrecognized or empty
+    pub format: Option<String>,
+    /// Format-specific fields, in display order
+    pub fields: Vec<FormatField>,
+}
+
+/// File system details of an input opened by path
+#[derive(Debug, Clone)]
+pub struct FileInfo {
+    pub size: u64,
+    /// Logical sector size of a block device
+    pub sector_size: Option<usize>,
+    /// Unix timestamps in seconds; `None` where the platform has none
+    pub created: Option<i64>,
+    pub modified: Option<i64>,
+    pub accessed: Option<i64>,
+    /// Octal mode such as `0644` on Unix, `read-only` / `read-write` elsewhere
+    pub permissions: String,
+}
+
+impl Metadata {
+    /// Gather metadata for the file at `path`
+    pub fn of_file(path: &Path) -> io::Result<Self> {
+        let metadata = fs::metadata(path)?;
+        let mut file = fs::File::open(path)?;
+        let sector = sector_size(&file)?;
+        let file_info = FileInfo {
+            size: input_len(&file)?,
+            sector_size: sector,
+            created: unix_seconds(metadata.created()),
+            modified: unix_seconds(metadata.modified()),
+            accessed: unix_seconds(metadata.accessed()),
+            permissions: format_permissions(&metadata.permissions()),
+        };
+
+        // Read first 1024 bytes for format detection
+        let mut buffer = [0; FORMAT_PREFIX_LEN];
+        let bytes_read = match sector {
+            Some(sector) => read_full(&mut SectorReader::new(file, 0, sector)?, &mut buffer)?,
+            None => read_full(&mut file, &mut buffer)?,
+        };
+        let mut result = Self::of_stream(path.display().to_string(), None, None, &buffer[..bytes_read]);
+        result.kind = Some(FileKind::of(&metadata.file_type()));
+        result.file_info = Some(file_info);
+        Ok(result)
+    }
+
+    /// Metadata for a non-seekable input (stdin, FIFO, character device) or
+    /// for the decompressed payload of a compressed input
+    ///
+    /// Streams have no meaningful size, timestamps or permissions, so only
+    /// the name, the kind of file and the format fields detected from
+    /// `prefix` are known. Callers should pass the first
+    /// [`FORMAT_PREFIX_LEN`] bytes of the stream and, when it was
+    /// decompressed on the fly, the format tag of the compressor.
+    pub fn of_stream(name: String, kind: Option<FileKind>, compression: Option<&str>, prefix: &[u8]) -> Self {
+        let format = Some(detect_file_format(prefix)).filter(|format| !prefix.is_empty() && !format.is_empty());
+        Metadata {
+            file: name,
+            kind,
+            file_info: None,
+            compression: compression.map(str::to_string),
+            format: format.map(str::to_string),
+            fields: if prefix.is_empty() { Vec::new() } else { format_fields(prefix) },
+        }
+    }
+
+    /// Labels and values shown above the format fields, as text and HTML
+    /// render them
+    pub fn entries(&self) -> Vec<(&'static str, String)> {
+        let mut entries = vec![("File", self.file.clone())];
+        match &self.file_info {
+            Some(info) => {
+                entries.push(("Size", format!("{} bytes", info.size)));
+                if let Some(kind) = self.kind.filter(|&kind| kind != FileKind::Regular) {
+                    entries.push(("Type", kind.describe().to_string()));
+                }
+                if let Some(sector) = info.sector_size {
+                    entries.push(("Sector size", format!("{} bytes", sector)));
+                }
+                entries.push(("Created", format_timestamp(info.created)));
+                entries.push(("Modified", format_timestamp(info.modified)));
+                entries.push(("Accessed", format_timestamp(info.accessed)));
+                entries.push(("Permissions", info.permissions.clone()));
+            }
+            None => entries.push(("Type", self.kind.map_or("stream", FileKind::describe).to_string())),
+        }
+        if let Some(compression) = &self.compression {
+            entries.push(("Compression", format!("{} (showing decompressed data)", compression)));
+        }
+        if let Some(format) = &self.format {
+            entries.push(("Format", format.clone()));
+        }
+        entries
+    }
+
+    /// Print `Label: value` lines followed by a blank line, coloring the
+    /// labels when a theme is given
+    pub fn print(&self, theme: Option<&Theme>) {
+        for (label, value) in self.entries() {
+            print_field(theme, &format!("{}: {}", label, value));
+        }
+        for field in &self.fields {
+            print_field(theme, &format!("  {}", field));
+        }
+        println!();
+    }
+
+    /// The `{"type":"metadata",...}` record of `--format json`
+    ///
+    /// Every key is always present; values that do not apply to the input
+    /// are `null`. Format fields are keyed by [`FormatField::key`].
+    pub fn to_json(&self) -> serde_json::Value {
+        let info = self.file_info.as_ref();
+        let fields: serde_json::Map<String, serde_json::Value> =
+            self.fields.iter().map(|field| (field.key(), field.value.to_json())).collect();
+        json!({
+            "type": "metadata",
+            "file": self.file,
+            "kind": self.kind.map_or("stream", FileKind::describe),
+            "size": info.map(|info| info.size),
+            "sector_size": info.and_then(|info| info.sector_size),
+            "created": info.and_then(|info| info.created),
+            "modified": info.and_then(|info| info.modified),
+            "accessed": info.and_then(|info| info.accessed),
+            "permissions": info.map(|info| &info.permissions),
+            "compression": self.compression,
+            "format": self.format,
+            "fields": fields,
+        })
+    }
+}
+
 /// Print file metadata including format information
 ///
 /// # Arguments
 /// * `path` - Path to the file
+/// * `theme` - Colors the field names; `None` prints plain text
 ///
 /// # Returns
 /// `io::Result<()>` - Ok on success, Err on I/O error
-pub fn print_metadata(path: &Path) -> io::Result<()> {
-    let metadata = fs::metadata(path)?;
-    println!("File: {}", path.display());
-    println!("Size: {} bytes", metadata.len());
-    println!("Created: {}", format_system_time(metadata.created()));
-    println!("Modified: {}", format_system_time(metadata.modified()));
-    println!("Accessed: {}", format_system_time(metadata.accessed()));
-    println!("Permissions: {}", format_permissions(&metadata.permissions()));
-
-    // Read first 1024 bytes for format detection
-    let mut file = fs::File::open(path)?;
-    let mut buffer = [0; 1024];
-    let bytes_read = file.read(&mut buffer)?;
-
-    if bytes_read > 0 {
-        let format_info = detect_file_format(&buffer[..bytes_read]);
-        if !format_info.is_empty() {
-            println!("Format: {}", format_info);
-        }
+pub fn print_metadata(path: &Path, theme: Option<&Theme>) -> io::Result<()> {
+    Metadata::of_file(path)?.print(theme);
+    Ok(())
+}
 
-        // Extract additional format-specific metadata
-        let additional_meta = extract_format_metadata(&buffer[..bytes_read]);
-        for line in additional_meta {
-            println!("{}", line);
-        }
+/// Print a `Label: value` line, coloring the label when a theme is given
+fn print_field(theme: Option<&Theme>, line: &str) {
+    match (theme, line.split_once(':')) {
+        (Some(theme), Some((label, value))) => println!("{}:{}", label.color(theme.label), value),
+        _ => println!("{}", line),
     }
-
-    println!();
-    Ok(())
 }
 
 #[cfg(test)]
@@ -76,15 +218,48 @@ mod tests {
     fn test_print_metadata_file_exists() -> io::Result<()> {
         let temp_file = NamedTempFile::new()?;
         // Just ensure it doesn't panic
-        let result = print_metadata(temp_file.path());
+        let result = print_metadata(temp_file.path(), None);
         assert!(result.is_ok());
         Ok(())
     }
 
+    #[test]
+    fn test_metadata_json_for_file() -> io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        std::io::Write::write_all(&mut temp_file, b"BZh9\x31\x41\x59\x26\x53\x59")?;
+        let record = Metadata::of_file(temp_file.path())?.to_json();
+        assert_eq!(record["type"], "metadata");
+        assert_eq!(record["kind"], "regular file");
+        assert_eq!(record["size"], 10);
+        assert!(record["sector_size"].is_null());
+        assert_eq!(record["format"], "BZIP2");
+        assert_eq!(record["fields"], json!({ "block_size": 900000 }));
+        Ok(())
+    }
+
+    #[test]
+    fn test_metadata_json_for_stream() {
+        let record = Metadata::of_stream("<stdin>".to_string(), None, Some("GZIP"), b"").to_json();
+        assert_eq!(record["kind"], "stream");
+        assert_eq!(record["compression"], "GZIP");
+        // Every key is present, with null where it does not apply
+        for key in ["size", "sector_size", "created", "modified", "accessed", "permissions", "format"] {
+            assert!(record[key].is_null(), "{key}");
+        }
+        assert_eq!(record["fields"], json!({}));
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(Some(1700000000)), "1700000000 (unix)");
+        assert_eq!(format_timestamp(Some(-1)), "<pre-epoch>");
+        assert_eq!(format_timestamp(None), "<unavailable>");
+    }
+
     #[test]
     fn test_print_metadata_nonexistent() {
         let path = Path::new("/nonexistent/file");
-        let result = print_metadata(path);
+        let result = print_metadata(path, None);
         assert!(result.is_err());
     }
 }
\ No newline at end of file
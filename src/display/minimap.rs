@@ -2,7 +2,9 @@
 
 use std::path::Path;
 use std::io;
-use image::{ImageReader, GenericImageView};
+use colored::{Color, Colorize};
+use image::{GenericImageView, ImageReader, Rgb, RgbImage};
+use crate::display::theme::Theme;
 use crate::utils::color::rgb_to_256;
 
 /// Display a minimap of an image file
@@ -11,39 +13,89 @@ use crate::utils::color::rgb_to_256;
 /// * `path` - Path to the image file
 /// * `rows` - Number of rows in the minimap
 /// * `cols` - Number of columns in the minimap
+/// * `theme` - Supplies the optional background drawn behind each cell; `None`
+///   draws the image in plain ASCII shades instead of 256-color blocks
 ///
 /// # Returns
 /// `io::Result<()>` - Ok on success, Err if image cannot be decoded
-pub fn display_minimap(path: &Path, rows: usize, cols: usize) -> io::Result<()> {
+pub fn display_minimap(path: &Path, rows: usize, cols: usize, theme: Option<&Theme>) -> io::Result<()> {
+    let pixels = minimap_pixels(path, rows, cols)?;
+    for row in 0..rows {
+        for col in 0..cols {
+            let pixel = pixels.get_pixel(col as u32, row as u32);
+            let Some(theme) = theme else {
+                print!("{}", shade(pixel[0], pixel[1], pixel[2]));
+                continue;
+            };
+            print!("{}", cell(rgb_to_256(pixel[0], pixel[1], pixel[2]), theme.minimap_background));
+        }
+        println!();
+    }
+    Ok(())
+}
+
+/// Sample the image at `path` on a `rows` x `cols` grid, one pixel per
+/// minimap cell (alpha is dropped)
+///
+/// # Returns
+/// A `cols` x `rows` image, or Err if the image cannot be decoded
+pub fn minimap_pixels(path: &Path, rows: usize, cols: usize) -> io::Result<RgbImage> {
     let img = ImageReader::open(path)?
         .decode()
         .map_err(|e| io::Error::other(format!("Failed to decode image: {}", e)))?;
 
     let (width, height) = img.dimensions();
+    Ok(RgbImage::from_fn(cols as u32, rows as u32, |col, row| {
+        let x = (col as usize * width as usize) / cols;
+        let y = (row as usize * height as usize) / rows;
+        let pixel = img.get_pixel(x as u32, y as u32);
+        Rgb([pixel[0], pixel[1], pixel[2]])
+    }))
+}
 
-    // Sample pixels
-    for row in 0..rows {
-        for col in 0..cols {
-            let x = (col * width as usize) / cols;
-            let y = (row * height as usize) / rows;
-            let pixel = img.get_pixel(x as u32, y as u32);
-            let color_idx = rgb_to_256(pixel[0], pixel[1], pixel[2]);
-            // Use ANSI 256-color escape sequence: \x1b[38;5;{index}m
-            print!("\x1b[38;5;{}m█\x1b[0m", color_idx);
-        }
-        println!();
+/// One minimap cell in 256-color `index`
+///
+/// A full block hides the cell's background, so with one set the pixel
+/// takes the upper half and the background shows in the lower half.
+fn cell(index: u8, background: Option<Color>) -> String {
+    match background {
+        // Use ANSI 256-color escape sequence: \x1b[38;5;{index}m
+        Some(background) => format!("\x1b[38;5;{}m{}\x1b[0m", index, "▀".on_color(background)),
+        None => format!("\x1b[38;5;{}m█\x1b[0m", index),
     }
-    Ok(())
+}
+
+/// ASCII stand-in for a pixel when color is off, denser for brighter pixels
+fn shade(r: u8, g: u8, b: u8) -> char {
+    const RAMP: &[u8] = b" .:-=+*#%@";
+    let luma = r as f32 * 0.299 + g as f32 * 0.587 + b as f32 * 0.114;
+    RAMP[((luma / 255.0) * (RAMP.len() - 1) as f32).round() as usize] as char
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::display::with_color_override;
+
+    #[test]
+    fn test_shade_ramp() {
+        assert_eq!(shade(0, 0, 0), ' ');
+        assert_eq!(shade(255, 255, 255), '@');
+        assert_eq!(shade(128, 128, 128), '+');
+    }
+
+    #[test]
+    fn test_cell_leaves_room_for_background() {
+        assert_eq!(cell(196, None), "\x1b[38;5;196m█\x1b[0m");
+        let out = with_color_override(true, || cell(196, Some(Color::White)));
+        assert!(out.starts_with("\x1b[38;5;196m"), "{out}");
+        assert!(out.contains("\x1b[47m▀"), "background should show under a half block: {out}");
+    }
 
     #[test]
     fn test_display_minimap_invalid_path() {
         let path = Path::new("/nonexistent/file.png");
-        let result = display_minimap(path, 8, 12);
+        let result = display_minimap(path, 8, 12, Some(&Theme::default()));
         assert!(result.is_err());
     }
 
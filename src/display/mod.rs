@@ -1,9 +1,50 @@
 //! Display functions for hex dumps and minimaps
 
+pub mod encoding;
 pub mod hex;
+pub mod highlight;
+pub mod html;
+pub mod literal;
 pub mod minimap;
 pub mod metadata;
+pub mod palette;
+pub mod reverse;
+pub mod theme;
+pub mod typed;
 
-pub use hex::{display_hex, write_hex};
-pub use minimap::display_minimap;
-pub use metadata::print_metadata;
\ No newline at end of file
+pub use encoding::Encoding;
+pub use hex::{display_hex, write_hex, write_region_header, Endian, HexOptions, HexWriter, Radix, Style};
+pub use highlight::Highlight;
+pub use html::{
+    write_html_head, write_html_metadata, write_html_minimap, write_html_section_end, write_html_section_start,
+    write_html_tail,
+};
+pub use literal::{write_literal, Language, LiteralWriter};
+pub use minimap::{display_minimap, minimap_pixels};
+pub use metadata::{print_metadata, Metadata};
+pub use palette::{ByteClass, Palette};
+pub use reverse::reverse_hex;
+pub use theme::Theme;
+pub use typed::DataType;
+/// Run `f` with `colored`'s process-wide override forced to `enabled`
+///
+/// Tests run in parallel, so every test that touches the override goes
+/// through here: a lock makes them take turns, and the override is reset
+/// afterwards, even if `f` panics.
+#[cfg(test)]
+pub(crate) fn with_color_override<T>(enabled: bool, f: impl FnOnce() -> T) -> T {
+    use std::sync::Mutex;
+
+    struct Reset;
+    impl Drop for Reset {
+        fn drop(&mut self) {
+            colored::control::unset_override();
+        }
+    }
+
+    static COLOR_LOCK: Mutex<()> = Mutex::new(());
+    let _lock = COLOR_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let _reset = Reset;
+    colored::control::set_override(enabled);
+    f()
+}
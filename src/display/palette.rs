@@ -0,0 +1,193 @@
+//! Byte classes and the colors used for them
+
+use colored::Color;
+
+/// Broad category of a byte, used to color its hex cell and glyph
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteClass {
+    /// 0x00
+    Null,
+    /// Printable ASCII, 0x21..=0x7e
+    Printable,
+    /// ASCII whitespace: space, tab, newline, vertical tab, form feed, carriage return
+    Whitespace,
+    /// Any other ASCII control byte
+    Control,
+    /// 0x80..=0xfe
+    NonAscii,
+    /// 0xff
+    Ff,
+}
+
+impl ByteClass {
+    /// Every class, in palette order
+    pub const ALL: [ByteClass; 6] = [
+        ByteClass::Null,
+        ByteClass::Printable,
+        ByteClass::Whitespace,
+        ByteClass::Control,
+        ByteClass::NonAscii,
+        ByteClass::Ff,
+    ];
+
+    /// Classify a single byte
+    pub fn of(byte: u8) -> Self {
+        match byte {
+            0x00 => ByteClass::Null,
+            0xff => ByteClass::Ff,
+            b' ' | b'\t' | b'\n' | 0x0b | 0x0c | b'\r' => ByteClass::Whitespace,
+            0x21..=0x7e => ByteClass::Printable,
+            0x01..=0x7f => ByteClass::Control,
+            _ => ByteClass::NonAscii,
+        }
+    }
+
+    /// Parse a class name as used in `--palette`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "null" | "zero" => Some(ByteClass::Null),
+            "printable" | "ascii" => Some(ByteClass::Printable),
+            "whitespace" | "space" => Some(ByteClass::Whitespace),
+            "control" => Some(ByteClass::Control),
+            "nonascii" | "non-ascii" | "high" => Some(ByteClass::NonAscii),
+            "ff" => Some(ByteClass::Ff),
+            _ => None,
+        }
+    }
+
+    /// Canonical name, as accepted by [`from_name`](Self::from_name)
+    pub fn name(self) -> &'static str {
+        match self {
+            ByteClass::Null => "null",
+            ByteClass::Printable => "printable",
+            ByteClass::Whitespace => "whitespace",
+            ByteClass::Control => "control",
+            ByteClass::NonAscii => "nonascii",
+            ByteClass::Ff => "ff",
+        }
+    }
+}
+
+/// Color for each [`ByteClass`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Palette {
+    pub null: Color,
+    pub printable: Color,
+    pub whitespace: Color,
+    pub control: Color,
+    pub non_ascii: Color,
+    pub ff: Color,
+}
+
+impl Default for Palette {
+    /// Close to hexyl's scheme, with 0xff picked out in red
+    fn default() -> Self {
+        Palette {
+            null: Color::BrightBlack,
+            printable: Color::Cyan,
+            whitespace: Color::Green,
+            control: Color::Magenta,
+            non_ascii: Color::Yellow,
+            ff: Color::Red,
+        }
+    }
+}
+
+impl Palette {
+    /// Color used for bytes of `class`
+    pub fn color(&self, class: ByteClass) -> Color {
+        match class {
+            ByteClass::Null => self.null,
+            ByteClass::Printable => self.printable,
+            ByteClass::Whitespace => self.whitespace,
+            ByteClass::Control => self.control,
+            ByteClass::NonAscii => self.non_ascii,
+            ByteClass::Ff => self.ff,
+        }
+    }
+
+    pub(crate) fn color_mut(&mut self, class: ByteClass) -> &mut Color {
+        match class {
+            ByteClass::Null => &mut self.null,
+            ByteClass::Printable => &mut self.printable,
+            ByteClass::Whitespace => &mut self.whitespace,
+            ByteClass::Control => &mut self.control,
+            ByteClass::NonAscii => &mut self.non_ascii,
+            ByteClass::Ff => &mut self.ff,
+        }
+    }
+
+    /// Apply `class=color` overrides, comma separated (e.g. `null=blue,ff=#ff8800`)
+    pub fn with_overrides(mut self, spec: &str) -> Result<Self, String> {
+        for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (class, color) = entry
+                .split_once('=')
+                .ok_or_else(|| format!("invalid palette entry '{}', expected CLASS=COLOR", entry))?;
+            let class = ByteClass::from_name(class.trim()).ok_or_else(|| {
+                format!(
+                    "unknown byte class '{}', expected null, printable, whitespace, control, nonascii or ff",
+                    class.trim()
+                )
+            })?;
+            *self.color_mut(class) =
+                parse_color(color.trim()).ok_or_else(|| format!("unknown color '{}'", color.trim()))?;
+        }
+        Ok(self)
+    }
+}
+
+/// Parse a color name (`red`, `bright black`, `bright-black`) or `#rrggbb`
+pub fn parse_color(name: &str) -> Option<Color> {
+    if let Some(hex) = name.strip_prefix('#') {
+        if hex.len() != 6 || !hex.is_ascii() {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        return Some(Color::TrueColor { r: channel(0)?, g: channel(2)?, b: channel(4)? });
+    }
+    name.replace(['-', '_'], " ").parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_byte_class_of() {
+        assert_eq!(ByteClass::of(0x00), ByteClass::Null);
+        assert_eq!(ByteClass::of(b'A'), ByteClass::Printable);
+        assert_eq!(ByteClass::of(b'~'), ByteClass::Printable);
+        assert_eq!(ByteClass::of(b' '), ByteClass::Whitespace);
+        assert_eq!(ByteClass::of(b'\n'), ByteClass::Whitespace);
+        assert_eq!(ByteClass::of(0x07), ByteClass::Control);
+        assert_eq!(ByteClass::of(0x7f), ByteClass::Control);
+        assert_eq!(ByteClass::of(0x80), ByteClass::NonAscii);
+        assert_eq!(ByteClass::of(0xfe), ByteClass::NonAscii);
+        assert_eq!(ByteClass::of(0xff), ByteClass::Ff);
+    }
+
+    #[test]
+    fn test_palette_overrides() {
+        let palette = Palette::default().with_overrides("null=blue, ff=#ff8800,high=bright-white").unwrap();
+        assert_eq!(palette.null, Color::Blue);
+        assert_eq!(palette.ff, Color::TrueColor { r: 0xff, g: 0x88, b: 0x00 });
+        assert_eq!(palette.non_ascii, Color::BrightWhite);
+        assert_eq!(palette.printable, Palette::default().printable);
+    }
+
+    #[test]
+    fn test_palette_overrides_errors() {
+        assert!(Palette::default().with_overrides("null").is_err());
+        assert!(Palette::default().with_overrides("bogus=red").is_err());
+        assert!(Palette::default().with_overrides("null=chartreuse").is_err());
+        assert!(Palette::default().with_overrides("null=#12345").is_err());
+    }
+
+    #[test]
+    fn test_parse_color() {
+        assert_eq!(parse_color("bright black"), Some(Color::BrightBlack));
+        assert_eq!(parse_color("bright_cyan"), Some(Color::BrightCyan));
+        assert_eq!(parse_color("#000000"), Some(Color::TrueColor { r: 0, g: 0, b: 0 }));
+        assert_eq!(parse_color("#zzzzzz"), None);
+    }
+}
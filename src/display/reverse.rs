@@ -0,0 +1,301 @@
+//! Turning hex dumps back into bytes (`--reverse`)
+
+use std::io::{self, BufRead, Write};
+
+use super::hex::{HexOptions, Radix, Style};
+use super::typed::DataType;
+
+/// Parse a dump made with `options` and write the bytes it shows
+///
+/// `options.style` picks the row layout. Rows in hhead's own layout are read
+/// with the dump's `radix`, `word_size` and `endian`; `xxd` rows are always
+/// hex in file order. Color escapes and `--highlight` brackets are ignored,
+/// a `*` line repeats the row before it up to the next offset (or up to the
+/// bare end offset that closes a run at the end of a dump), and lines
+/// without a leading hex offset (such as `==> name <==` headers) are skipped.
+/// An `--as` column is skipped too, but an `--as-only` dump has no bytes to
+/// read back.
+/// Decimal cells are space padded, so a partial trailing word in a decimal
+/// dump comes back at full `word_size`.
+///
+/// Returns the number of bytes written.
+pub fn reverse_hex<R: BufRead, W: Write>(input: R, out: &mut W, options: &HexOptions) -> io::Result<u64> {
+    if matches!(options.style, Style::Json | Style::Html) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "JSON and HTML rows cannot be reversed"));
+    }
+    if options.typed_only && options.typed.is_some() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "--as-only rows cannot be reversed"));
+    }
+    let mut position: Option<u64> = None;
+    let mut previous: Vec<u8> = Vec::new();
+    let mut squeezed = false;
+    let mut written = 0;
+    for (number, line) in input.lines().enumerate() {
+        let line = strip_ansi(&line?);
+        let invalid = |message: String| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", number + 1, message))
+        };
+        let line = line.trim_end();
+        if line == "*" {
+            squeezed = true;
+            continue;
+        }
+        let (offset, bytes) = if let Some((offset, row)) = split_row(line) {
+            (offset, parse_row(row, options).map_err(invalid)?)
+        } else if squeezed && let Some(offset) = parse_offset(line) {
+            (offset, Vec::new())
+        } else {
+            continue;
+        };
+
+        let expected = *position.get_or_insert(offset);
+        if offset < expected {
+            return Err(invalid(format!("offset {:x} goes backwards", offset)));
+        }
+        // Only a `*` run may skip ahead, by whole copies of the row before it
+        let gap = offset - expected;
+        if gap > 0 {
+            if !squeezed || previous.is_empty() || !gap.is_multiple_of(previous.len() as u64) {
+                return Err(invalid(format!("missing bytes before offset {:x}", offset)));
+            }
+            for _ in 0..gap / previous.len() as u64 {
+                out.write_all(&previous)?;
+            }
+        }
+        out.write_all(&bytes)?;
+        written += gap + bytes.len() as u64;
+        position = Some(offset + bytes.len() as u64);
+        previous = bytes;
+        squeezed = false;
+    }
+    Ok(written)
+}
+
+/// Split `OFFSET: rest` into the offset and the rest of the row
+fn split_row(line: &str) -> Option<(u64, &str)> {
+    let (offset, row) = line.split_once(':')?;
+    Some((parse_offset(offset)?, row))
+}
+
+/// A row offset: hex digits only, without a `0x` prefix
+fn parse_offset(text: &str) -> Option<u64> {
+    if text.is_empty() || !text.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    u64::from_str_radix(text, 16).ok()
+}
+
+/// Bytes shown in the cells of one row
+fn parse_row(row: &str, options: &HexOptions) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    match options.style {
+        // Cells run up to the `|` framing the character column, or up to
+        // the `--as` column, which starts after their fixed width; highlight
+        // brackets separate cells just like spaces
+        Style::Hhead => {
+            let row = match options.typed {
+                Some(typed) => row.get(..cells_width(row, typed, options)).unwrap_or(row),
+                None => row,
+            };
+            let cells = row.split('|').next().unwrap_or_default();
+            let tokens = cells.split(|c: char| c.is_whitespace() || c == '[' || c == ']');
+            for token in tokens.filter(|token| !token.is_empty()) {
+                let size = match options.radix {
+                    Radix::Decimal => Some(options.word_size),
+                    radix => (1..=options.word_size).find(|&size| radix.digits(size) == token.len()),
+                };
+                let value = size.and_then(|size| {
+                    let value = u64::from_str_radix(token, options.radix.base()).ok()?;
+                    (size == 8 || value >> (8 * size) == 0).then_some((value, size))
+                });
+                let (value, size) = value.ok_or_else(|| format!("invalid cell '{}'", token))?;
+                bytes.extend(options.endian.write(value, size));
+            }
+        }
+        // Hex pairs in file order, up to the two spaces before the ASCII column
+        Style::Xxd => {
+            let row = row.strip_prefix(' ').unwrap_or(row);
+            let cells = row.split("  ").next().unwrap_or_default();
+            for token in cells.split_whitespace() {
+                if !token.len().is_multiple_of(2) || !token.is_ascii() {
+                    return Err(format!("invalid cell '{}'", token));
+                }
+                for i in (0..token.len()).step_by(2) {
+                    let pair = &token[i..i + 2];
+                    bytes.push(u8::from_str_radix(pair, 16).map_err(|_| format!("invalid cell '{}'", token))?);
+                }
+            }
+        }
+        Style::Json | Style::Html => unreachable!("rejected by reverse_hex"),
+    }
+    Ok(bytes)
+}
+
+/// Characters taken by the byte cells of a hhead row with a `typed` column
+///
+/// Cells come in groups of 8 bytes, each a run of space-led cells plus one
+/// more space, and the typed column adds a space-led value per element and
+/// one more space before ` |`. Both only grow with the dump's width, so the
+/// `|` framing the character column gives it away without `--width`; widths
+/// that put the frame in the same place also lay out the same cells.
+fn cells_width(row: &str, typed: DataType, options: &HexOptions) -> usize {
+    let frame = row.find('|').unwrap_or(row.len());
+    let cells_per_group = 8usize.div_ceil(options.word_size);
+    let group = cells_per_group * (options.radix.digits(options.word_size) + 1) + 1;
+    let layout = |width: usize| {
+        let cells = width.div_ceil(8) * group;
+        (cells, cells + width / typed.size() * (typed.cell_width() + 1) + 2)
+    };
+    (1..=frame).map(layout).find(|&(_, end)| end >= frame).map_or(row.len(), |(cells, _)| cells)
+}
+
+/// Drop ANSI CSI sequences such as `\x1b[36m`
+fn strip_ansi(line: &str) -> String {
+    let mut text = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            text.push(c);
+        } else if chars.next() == Some('[') {
+            // Parameters up to the final byte, `@` through `~`
+            for c in chars.by_ref() {
+                if ('@'..='~').contains(&c) {
+                    break;
+                }
+            }
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::hex::{Endian, HexWriter};
+    use crate::display::{with_color_override, Highlight};
+
+    fn dump(data: &[u8], options: &HexOptions) -> String {
+        let mut buf = Vec::new();
+        let mut writer = HexWriter::new(&mut buf, options.clone(), 0);
+        writer.set_end_offset(data.len() as u64);
+        writer.write(data).unwrap();
+        writer.finish().unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    fn reverse(text: &str, options: &HexOptions) -> io::Result<Vec<u8>> {
+        let mut out = Vec::new();
+        let written = reverse_hex(text.as_bytes(), &mut out, options)?;
+        assert_eq!(written, out.len() as u64);
+        Ok(out)
+    }
+
+    fn sample() -> Vec<u8> {
+        let mut data: Vec<u8> = (0..=255u8).collect();
+        data.extend_from_slice(&[0u8; 200]);
+        data.extend_from_slice("|a] [b| 世界\n".as_bytes());
+        data
+    }
+
+    #[test]
+    fn test_round_trip_layouts() {
+        let data = sample();
+        let layouts = [
+            HexOptions::new(16),
+            HexOptions { squeeze: true, ..HexOptions::new(16) },
+            HexOptions { squeeze: true, ..HexOptions::new(7) },
+            HexOptions { radix: Radix::Octal, ..HexOptions::new(8) },
+            HexOptions { radix: Radix::Decimal, ..HexOptions::new(16) },
+            HexOptions { radix: Radix::Binary, word_size: 2, ..HexOptions::new(8) },
+            HexOptions { word_size: 4, endian: Endian::Big, ..HexOptions::new(16) },
+            HexOptions { word_size: 8, ..HexOptions::new(32) },
+            HexOptions { style: Style::Xxd, ..HexOptions::new(16) },
+            HexOptions { style: Style::Xxd, ..HexOptions::new(5) },
+            HexOptions { typed: Some(DataType::U32), ..HexOptions::new(16) },
+            HexOptions { typed: Some(DataType::F64), radix: Radix::Octal, word_size: 2, ..HexOptions::new(8) },
+            HexOptions { typed: Some(DataType::I8), squeeze: true, ..HexOptions::new(7) },
+        ];
+        for options in layouts {
+            for len in [0, 1, 17, data.len()] {
+                let text = dump(&data[..len], &options);
+                assert_eq!(reverse(&text, &options).unwrap(), &data[..len], "{:?}\n{}", options, text);
+            }
+        }
+
+        // The `--as` column is found from the rows, whatever `width` says
+        for width in [8, 12, 16, 40] {
+            let options = HexOptions { typed: Some(DataType::U32), ..HexOptions::new(width) };
+            let text = dump(&data, &options);
+            assert_eq!(reverse(&text, &HexOptions { width: 64, ..options }).unwrap(), data, "width {}", width);
+        }
+    }
+
+    #[test]
+    fn test_round_trip_colors_and_highlights() {
+        let data = sample();
+        let options = HexOptions {
+            color: true,
+            squeeze: true,
+            highlights: vec![Highlight::from_spec("3:40").unwrap(), Highlight::from_spec("300:2").unwrap()],
+            ..HexOptions::new(16)
+        };
+        let text = with_color_override(true, || dump(&data, &options));
+        assert!(text.contains("\x1b["));
+        assert_eq!(reverse(&text, &options).unwrap(), data);
+
+        let plain = HexOptions { color: false, ..options };
+        let text = dump(&data, &plain);
+        assert!(text.contains('['));
+        assert_eq!(reverse(&text, &plain).unwrap(), data);
+    }
+
+    #[test]
+    fn test_reverse_xxd_output() {
+        let text = "\
+00000000: 4865 6c6c 6f2c 2057 6f72 6c64 210a 6162  Hello, World!.ab
+00000010: 7c7c                                     ||
+";
+        let options = HexOptions { style: Style::Xxd, ..HexOptions::new(16) };
+        assert_eq!(reverse(text, &options).unwrap(), b"Hello, World!\nab||");
+    }
+
+    #[test]
+    fn test_reverse_starts_at_first_offset_and_skips_other_lines() {
+        let text = "==> a.bin <==\n00000100: 61 62  |ab|\n00000102: 63  |c|\n";
+        assert_eq!(reverse(text, &HexOptions::new(2)).unwrap(), b"abc");
+    }
+
+    #[test]
+    fn test_reverse_run_closed_by_end_offset() {
+        let text = "00000000: 61 62  |ab|\n*\n00000008\n";
+        assert_eq!(reverse(text, &HexOptions::new(2)).unwrap(), b"abababab");
+        // Outside a run a bare offset is just another line that isn't a row
+        assert_eq!(reverse("00000000: 61  |a|\n00000008\n", &HexOptions::new(2)).unwrap(), b"a");
+    }
+
+    #[test]
+    fn test_reverse_errors() {
+        let options = HexOptions::new(16);
+        let gap = "00000000: 61  |a|\n00000005: 62  |b|\n";
+        let err = reverse(gap, &options).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().starts_with("line 2: missing bytes"), "{err}");
+
+        let backwards = "00000004: 61  |a|\n00000000: 62  |b|\n";
+        assert!(reverse(backwards, &options).unwrap_err().to_string().contains("backwards"));
+
+        assert!(reverse("00000000: 6g  |a|\n", &options).is_err());
+        assert!(reverse("00000000: 616  |a|\n", &options).is_err());
+        let octal = HexOptions { radix: Radix::Octal, ..options.clone() };
+        assert!(reverse("00000000: 777  |.|\n", &octal).is_err());
+
+        let typed_only = HexOptions { typed: Some(DataType::U8), typed_only: true, ..options };
+        let err = reverse("00000000:   97  |a|\n", &typed_only).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_strip_ansi() {
+        assert_eq!(strip_ansi("\x1b[36m00000000\x1b[0m: \x1b[44;30m62\x1b[0m"), "00000000: 62");
+    }
+}
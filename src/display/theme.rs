@@ -0,0 +1,271 @@
+//! Color themes for hex, metadata and minimap output
+
+use colored::Color;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::palette::{parse_color, ByteClass, Palette};
+
+/// Names accepted by [`Theme::builtin`]
+pub const BUILTIN_THEMES: [&str; 4] = ["dark", "light", "high-contrast", "colorblind"];
+
+/// Every color used when `--color` is on
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Theme {
+    /// Row offsets
+    pub offset: Color,
+    /// `|` bars around the character column and the `*` squeeze marker
+    pub separator: Color,
+    /// Field names in `--meta` output
+    pub label: Color,
+    /// Background for `--highlight` ranges that name no color
+    pub highlight: Color,
+    /// Byte cells and glyphs, by byte class
+    pub palette: Palette,
+    /// Background behind minimap cells, so very dark or very light images
+    /// stay visible against the terminal; pixels are then drawn as upper
+    /// half blocks so it shows between rows
+    pub minimap_background: Option<Color>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            offset: Color::Cyan,
+            separator: Color::Magenta,
+            label: Color::BrightBlue,
+            highlight: Color::Yellow,
+            palette: Palette::default(),
+            minimap_background: None,
+        }
+    }
+}
+
+impl Theme {
+    /// Look up a built-in theme by name
+    pub fn builtin(name: &str) -> Option<Self> {
+        let rgb = |r, g, b| Color::TrueColor { r, g, b };
+        match name.to_ascii_lowercase().as_str() {
+            "dark" => Some(Theme::default()),
+            // Avoids yellow and cyan, which wash out on white backgrounds
+            "light" => Some(Theme {
+                offset: Color::Blue,
+                separator: Color::Magenta,
+                label: Color::Blue,
+                highlight: rgb(0xff, 0xd7, 0x5f),
+                palette: Palette {
+                    null: rgb(0x9e, 0x9e, 0x9e),
+                    printable: rgb(0x00, 0x5f, 0x87),
+                    whitespace: rgb(0x00, 0x87, 0x00),
+                    control: rgb(0x87, 0x00, 0x87),
+                    non_ascii: rgb(0xaf, 0x5f, 0x00),
+                    ff: rgb(0xaf, 0x00, 0x00),
+                },
+                minimap_background: Some(Color::Black),
+            }),
+            "high-contrast" => Some(Theme {
+                offset: Color::BrightWhite,
+                separator: Color::BrightWhite,
+                label: Color::BrightWhite,
+                highlight: Color::BrightYellow,
+                palette: Palette {
+                    null: Color::BrightBlue,
+                    printable: Color::BrightWhite,
+                    whitespace: Color::BrightGreen,
+                    control: Color::BrightMagenta,
+                    non_ascii: Color::BrightYellow,
+                    ff: Color::BrightRed,
+                },
+                minimap_background: None,
+            }),
+            // Okabe-Ito palette, distinguishable with the common color vision deficiencies
+            "colorblind" | "colorblind-safe" => Some(Theme {
+                offset: rgb(0x00, 0x72, 0xb2),
+                separator: rgb(0x99, 0x99, 0x99),
+                label: rgb(0x56, 0xb4, 0xe9),
+                highlight: rgb(0xf0, 0xe4, 0x42),
+                palette: Palette {
+                    null: rgb(0x99, 0x99, 0x99),
+                    printable: rgb(0x56, 0xb4, 0xe9),
+                    whitespace: rgb(0x00, 0x9e, 0x73),
+                    control: rgb(0xcc, 0x79, 0xa7),
+                    non_ascii: rgb(0xe6, 0x9f, 0x00),
+                    ff: rgb(0xd5, 0x5e, 0x00),
+                },
+                minimap_background: None,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Parse a TOML theme
+    ///
+    /// Every key is optional; `base` names the built-in theme to start from
+    /// (`dark` if absent):
+    ///
+    /// ```toml
+    /// base = "light"
+    /// offset = "blue"
+    /// separator = "#888888"
+    /// label = "blue"
+    /// highlight = "yellow"
+    /// minimap_background = "white"
+    ///
+    /// [palette]
+    /// null = "bright black"
+    /// ff = "red"
+    /// ```
+    pub fn from_toml(text: &str) -> Result<Self, String> {
+        let table: toml::Table = text.parse().map_err(|e: toml::de::Error| e.message().to_string())?;
+        let string = |key: &str, value: &toml::Value| {
+            value.as_str().map(str::to_string).ok_or_else(|| format!("'{}' must be a string", key))
+        };
+        let color = |key: &str, value: &toml::Value| {
+            let name = string(key, value)?;
+            parse_color(&name).ok_or_else(|| format!("unknown color '{}' for '{}'", name, key))
+        };
+
+        let mut theme = match table.get("base") {
+            Some(base) => {
+                let name = string("base", base)?;
+                Theme::builtin(&name).ok_or_else(|| format!("unknown base theme '{}'", name))?
+            }
+            None => Theme::default(),
+        };
+        for (key, value) in &table {
+            match key.as_str() {
+                "base" => {}
+                "offset" => theme.offset = color(key, value)?,
+                "separator" => theme.separator = color(key, value)?,
+                "label" => theme.label = color(key, value)?,
+                "highlight" => theme.highlight = color(key, value)?,
+                "minimap_background" => theme.minimap_background = Some(color(key, value)?),
+                "palette" => {
+                    let palette = value.as_table().ok_or("'palette' must be a table")?;
+                    for (class_name, value) in palette {
+                        let class = ByteClass::from_name(class_name)
+                            .ok_or_else(|| format!("unknown byte class '{}' in [palette]", class_name))?;
+                        *theme.palette.color_mut(class) = color(class_name, value)?;
+                    }
+                }
+                _ => return Err(format!("unknown theme key '{}'", key)),
+            }
+        }
+        Ok(theme)
+    }
+
+    /// Read a TOML theme file
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        Theme::from_toml(&text).map_err(|e| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), e))
+        })
+    }
+
+    /// `$XDG_CONFIG_HOME/hhead/theme.toml`, falling back to `~/.config`
+    pub fn config_path() -> Option<PathBuf> {
+        let base = env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+        Some(base.join("hhead").join("theme.toml"))
+    }
+
+    /// Resolve `--theme`: a built-in name or a path to a TOML file
+    ///
+    /// Without a name, the user's config theme is used if it exists, and
+    /// the `dark` theme otherwise.
+    pub fn select(name: Option<&str>) -> io::Result<Self> {
+        match name {
+            Some(name) => match Theme::builtin(name) {
+                Some(theme) => Ok(theme),
+                None if Path::new(name).exists() => Theme::load(Path::new(name)),
+                None => Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!(
+                        "unknown theme '{}', expected {} or a theme file",
+                        name,
+                        BUILTIN_THEMES.join(", ")
+                    ),
+                )),
+            },
+            None => match Theme::config_path().filter(|path| path.is_file()) {
+                Some(path) => Theme::load(&path),
+                None => Ok(Theme::default()),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_builtin_themes() {
+        for name in BUILTIN_THEMES {
+            assert!(Theme::builtin(name).is_some(), "{}", name);
+        }
+        assert_eq!(Theme::builtin("DARK"), Some(Theme::default()));
+        assert!(Theme::builtin("solarized").is_none());
+        assert_ne!(Theme::builtin("light"), Theme::builtin("dark"));
+    }
+
+    #[test]
+    fn test_from_toml() {
+        let theme = Theme::from_toml(
+            r##"
+            base = "light"
+            offset = "green"
+            highlight = "cyan"
+            minimap_background = "#ffffff"
+
+            [palette]
+            ff = "bright red"
+            "##,
+        )
+        .unwrap();
+        let light = Theme::builtin("light").unwrap();
+        assert_eq!(theme.offset, Color::Green);
+        assert_eq!(theme.separator, light.separator);
+        assert_eq!(theme.highlight, Color::Cyan);
+        assert_eq!(theme.minimap_background, Some(Color::TrueColor { r: 255, g: 255, b: 255 }));
+        assert_eq!(theme.palette.ff, Color::BrightRed);
+        assert_eq!(theme.palette.null, light.palette.null);
+    }
+
+    #[test]
+    fn test_from_toml_empty_is_default() {
+        assert_eq!(Theme::from_toml("").unwrap(), Theme::default());
+    }
+
+    #[test]
+    fn test_from_toml_errors() {
+        assert!(Theme::from_toml("offset = ").is_err());
+        assert!(Theme::from_toml("offset = 3").is_err());
+        assert!(Theme::from_toml("offset = \"mauve\"").is_err());
+        assert!(Theme::from_toml("base = \"nope\"").is_err());
+        assert!(Theme::from_toml("background = \"red\"").is_err());
+        assert!(Theme::from_toml("[palette]\nbogus = \"red\"").is_err());
+    }
+
+    #[test]
+    fn test_select() -> io::Result<()> {
+        assert_eq!(Theme::select(Some("high-contrast"))?, Theme::builtin("high-contrast").unwrap());
+        assert!(Theme::select(Some("/nonexistent/theme.toml")).is_err());
+
+        let mut file = NamedTempFile::new()?;
+        writeln!(file, "label = \"red\"")?;
+        let theme = Theme::select(Some(file.path().to_str().unwrap()))?;
+        assert_eq!(theme.label, Color::Red);
+
+        writeln!(file, "nonsense = 1")?;
+        let err = Theme::select(Some(file.path().to_str().unwrap())).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        Ok(())
+    }
+}
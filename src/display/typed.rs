@@ -0,0 +1,249 @@
+//! Decoding rows as typed values (integers, floats, timestamps)
+
+use std::fmt::{Display, LowerExp};
+
+use super::hex::Endian;
+
+/// Element type for the decoded value column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataType {
+    I8,
+    I16,
+    I32,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+    F16,
+    F32,
+    F64,
+    /// Signed 32-bit Unix time in seconds
+    Time32,
+    /// Signed 64-bit Unix time in seconds
+    Time64,
+}
+
+impl DataType {
+    /// Parse a type name such as `u16`, `f32` or `time64`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "i8" => Some(DataType::I8),
+            "i16" => Some(DataType::I16),
+            "i32" => Some(DataType::I32),
+            "i64" => Some(DataType::I64),
+            "u8" => Some(DataType::U8),
+            "u16" => Some(DataType::U16),
+            "u32" => Some(DataType::U32),
+            "u64" => Some(DataType::U64),
+            "f16" => Some(DataType::F16),
+            "f32" => Some(DataType::F32),
+            "f64" => Some(DataType::F64),
+            "time32" => Some(DataType::Time32),
+            "time64" => Some(DataType::Time64),
+            _ => None,
+        }
+    }
+
+    /// Size of one element in bytes
+    pub fn size(self) -> usize {
+        match self {
+            DataType::I8 | DataType::U8 => 1,
+            DataType::I16 | DataType::U16 | DataType::F16 => 2,
+            DataType::I32 | DataType::U32 | DataType::F32 | DataType::Time32 => 4,
+            DataType::I64 | DataType::U64 | DataType::F64 | DataType::Time64 => 8,
+        }
+    }
+
+    /// Column width every value is right-aligned to: the longest rendering
+    /// of any element
+    ///
+    /// Floats are widest with every significant digit after a sign and either
+    /// `0.000` (`-0.000100000005`) or, for f64, a three-digit exponent; f16
+    /// values are printed with f32's digits. Time64 spans years to ±2.9e11.
+    pub fn cell_width(self) -> usize {
+        match self {
+            DataType::I8 => 4,
+            DataType::U8 => 3,
+            DataType::I16 => 6,
+            DataType::U16 => 5,
+            DataType::I32 => 11,
+            DataType::U32 => 10,
+            DataType::I64 | DataType::U64 => 20,
+            DataType::F16 | DataType::F32 => 15,
+            DataType::F64 => 24,
+            DataType::Time32 => 20,
+            DataType::Time64 => 29,
+        }
+    }
+
+    /// Decode one element from exactly [`size`](Self::size) bytes
+    pub fn format(self, bytes: &[u8], endian: Endian) -> String {
+        debug_assert_eq!(bytes.len(), self.size());
+        let raw = endian.read(bytes);
+        // Sign-extend from the element width
+        let shift = 64 - 8 * self.size() as u32;
+        let signed = ((raw << shift) as i64) >> shift;
+        match self {
+            DataType::I8 | DataType::I16 | DataType::I32 | DataType::I64 => signed.to_string(),
+            DataType::U8 | DataType::U16 | DataType::U32 | DataType::U64 => raw.to_string(),
+            DataType::F16 => format_float(f16_to_f32(raw as u16), 1e5),
+            DataType::F32 => format_float(f32::from_bits(raw as u32), 1e9),
+            DataType::F64 => format_float(f64::from_bits(raw), 1e15),
+            DataType::Time32 | DataType::Time64 => format_unix_time(signed),
+        }
+    }
+}
+
+/// Plain notation for everyday magnitudes, scientific outside of them
+///
+/// Formatting in the value's own type keeps the shortest round-trip digits.
+fn format_float<T: Copy + Display + LowerExp + Into<f64>>(value: T, large: f64) -> String {
+    let wide: f64 = value.into();
+    if wide == 0.0 || !wide.is_finite() || (1e-4..large).contains(&wide.abs()) {
+        value.to_string()
+    } else {
+        format!("{:e}", value)
+    }
+}
+
+/// Widen an IEEE 754 half-precision value
+fn f16_to_f32(bits: u16) -> f32 {
+    let sign = u32::from(bits >> 15) << 31;
+    let exponent = u32::from((bits >> 10) & 0x1f);
+    let mantissa = u32::from(bits & 0x3ff);
+    let magnitude = match (exponent, mantissa) {
+        (0, 0) => 0,
+        // Subnormal: value is mantissa * 2^-24, exact in f32
+        (0, _) => {
+            let value = mantissa as f32 * 2f32.powi(-24);
+            return if sign != 0 { -value } else { value };
+        }
+        (0x1f, _) => 0x7f80_0000 | (mantissa << 13),
+        _ => ((exponent + 127 - 15) << 23) | (mantissa << 13),
+    };
+    f32::from_bits(sign | magnitude)
+}
+
+/// Seconds since the Unix epoch as an ISO 8601 UTC timestamp
+fn format_unix_time(seconds: i64) -> String {
+    let days = seconds.div_euclid(86_400);
+    let secs = seconds.rem_euclid(86_400);
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
+
+/// Proleptic Gregorian date for a count of days since 1970-01-01
+/// (Howard Hinnant's `civil_from_days`)
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_name_and_size() {
+        assert_eq!(DataType::from_name("U16"), Some(DataType::U16));
+        assert_eq!(DataType::from_name("time64"), Some(DataType::Time64));
+        assert_eq!(DataType::from_name("f128"), None);
+        assert_eq!(DataType::F16.size(), 2);
+        assert_eq!(DataType::Time32.size(), 4);
+        assert_eq!(DataType::I64.size(), 8);
+    }
+
+    #[test]
+    fn test_integers() {
+        assert_eq!(DataType::I8.format(&[0xff], Endian::Little), "-1");
+        assert_eq!(DataType::U8.format(&[0xff], Endian::Little), "255");
+        assert_eq!(DataType::I16.format(&[0x00, 0x80], Endian::Little), "-32768");
+        assert_eq!(DataType::U16.format(&[0x01, 0x02], Endian::Big), "258");
+        assert_eq!(DataType::I32.format(&[0xff, 0xff, 0xff, 0xfe], Endian::Big), "-2");
+        assert_eq!(DataType::U64.format(&[0xff; 8], Endian::Little), u64::MAX.to_string());
+        assert_eq!(DataType::I64.format(&[0xff; 8], Endian::Little), "-1");
+    }
+
+    #[test]
+    fn test_integer_widths_fit_extremes() {
+        assert!(i64::MIN.to_string().len() <= DataType::I64.cell_width());
+        assert!(i32::MIN.to_string().len() <= DataType::I32.cell_width());
+        assert!(i16::MIN.to_string().len() <= DataType::I16.cell_width());
+        assert!(i8::MIN.to_string().len() <= DataType::I8.cell_width());
+    }
+
+    #[test]
+    fn test_float_and_time_widths_fit_extremes() {
+        let width = |data_type: DataType, bytes: &[u8]| data_type.format(bytes, Endian::Little).len();
+        for bits in 0..=u16::MAX {
+            assert!(width(DataType::F16, &bits.to_le_bytes()) <= DataType::F16.cell_width());
+        }
+        for value in [-0.000100000005f32, f32::MIN, -f32::MIN_POSITIVE] {
+            assert!(width(DataType::F32, &value.to_le_bytes()) <= DataType::F32.cell_width());
+        }
+        for value in [-0.00010000000000000002f64, -2.2250738585072014e-308, f64::MIN] {
+            assert!(width(DataType::F64, &value.to_le_bytes()) <= DataType::F64.cell_width());
+        }
+        assert_eq!(width(DataType::F32, &(-0.000100000005f32).to_le_bytes()), DataType::F32.cell_width());
+        assert_eq!(width(DataType::F64, &(-2.2250738585072014e-308f64).to_le_bytes()), DataType::F64.cell_width());
+        for seconds in [i32::MIN, i32::MAX] {
+            assert!(width(DataType::Time32, &seconds.to_le_bytes()) <= DataType::Time32.cell_width());
+        }
+        assert_eq!(width(DataType::Time64, &i64::MIN.to_le_bytes()), DataType::Time64.cell_width());
+        assert!(width(DataType::Time64, &i64::MAX.to_le_bytes()) <= DataType::Time64.cell_width());
+    }
+
+    #[test]
+    fn test_floats() {
+        assert_eq!(DataType::F32.format(&1.5f32.to_le_bytes(), Endian::Little), "1.5");
+        assert_eq!(DataType::F32.format(&(-0.25f32).to_be_bytes(), Endian::Big), "-0.25");
+        assert_eq!(DataType::F32.format(&0.1f32.to_le_bytes(), Endian::Little), "0.1");
+        assert_eq!(DataType::F32.format(&3e-20f32.to_le_bytes(), Endian::Little), "3e-20");
+        assert_eq!(DataType::F64.format(&1e300f64.to_le_bytes(), Endian::Little), "1e300");
+        assert_eq!(DataType::F64.format(&f64::NAN.to_le_bytes(), Endian::Little), "NaN");
+        assert_eq!(DataType::F32.format(&f32::NEG_INFINITY.to_le_bytes(), Endian::Little), "-inf");
+    }
+
+    #[test]
+    fn test_f16() {
+        assert_eq!(f16_to_f32(0x3c00), 1.0);
+        assert_eq!(f16_to_f32(0xc000), -2.0);
+        assert_eq!(f16_to_f32(0x7bff), 65504.0);
+        assert_eq!(f16_to_f32(0x0001), 2f32.powi(-24));
+        assert_eq!(f16_to_f32(0x8001), -(2f32.powi(-24)));
+        assert!(f16_to_f32(0x7c00).is_infinite());
+        assert!(f16_to_f32(0x7e00).is_nan());
+        assert_eq!(DataType::F16.format(&[0x00, 0x3e], Endian::Little), "1.5");
+    }
+
+    #[test]
+    fn test_timestamps() {
+        assert_eq!(DataType::Time32.format(&[0; 4], Endian::Little), "1970-01-01T00:00:00Z");
+        assert_eq!(
+            DataType::Time32.format(&0x7fff_ffffu32.to_le_bytes(), Endian::Little),
+            "2038-01-19T03:14:07Z"
+        );
+        assert_eq!(DataType::Time32.format(&(-1i32).to_le_bytes(), Endian::Little), "1969-12-31T23:59:59Z");
+        assert_eq!(
+            DataType::Time64.format(&951_782_400i64.to_be_bytes(), Endian::Big),
+            "2000-02-29T00:00:00Z"
+        );
+    }
+}
@@ -28,6 +28,18 @@ pub fn detect_file_format(data: &[u8]) -> &'static str {
     if data.len() >= 2 && data.starts_with(b"\x1f\x8b") {
         return "GZIP";
     }
+    // Zstandard frame: requires at least 4 bytes
+    if data.len() >= 4 && data.starts_with(b"\x28\xb5\x2f\xfd") {
+        return "ZSTD";
+    }
+    // XZ: requires at least 6 bytes
+    if data.len() >= 6 && data.starts_with(b"\xfd7zXZ\x00") {
+        return "XZ";
+    }
+    // BZIP2: "BZh" followed by the block size digit '1'..='9'
+    if data.len() >= 4 && data.starts_with(b"BZh") && (b'1'..=b'9').contains(&data[3]) {
+        return "BZIP2";
+    }
     // TIFF: requires at least 4 bytes
     if data.len() >= 4 && (data.starts_with(b"II\x2a\x00") || data.starts_with(b"MM\x00\x2a")) {
         return "TIFF";
@@ -100,6 +112,27 @@ mod tests {
         assert_eq!(detect_file_format(gzip_header), "GZIP");
     }
 
+    #[test]
+    fn test_detect_zstd() {
+        assert_eq!(detect_file_format(b"\x28\xb5\x2f\xfd"), "ZSTD");
+        assert_eq!(detect_file_format(b"\x28\xb5\x2f"), "");
+    }
+
+    #[test]
+    fn test_detect_xz() {
+        assert_eq!(detect_file_format(b"\xfd7zXZ\x00\x00\x04"), "XZ");
+        assert_eq!(detect_file_format(b"\xfd7zXZ"), "");
+    }
+
+    #[test]
+    fn test_detect_bzip2() {
+        assert_eq!(detect_file_format(b"BZh9"), "BZIP2");
+        assert_eq!(detect_file_format(b"BZh1\x31\x41\x59\x26"), "BZIP2");
+        // Block size digit must be 1-9; plain "BZh" text is not a match
+        assert_eq!(detect_file_format(b"BZh0"), "");
+        assert_eq!(detect_file_format(b"BZhello"), "");
+    }
+
     #[test]
     fn test_detect_tiff() {
         let tiff_little = b"II\x2a\x00";
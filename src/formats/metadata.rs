@@ -1,36 +1,116 @@
 //! Format-specific metadata extraction
 
+use std::fmt;
+
+use serde_json::json;
+
 use super::detection::detect_file_format;
 
+/// Value of a format-specific field, typed for structured output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldValue {
+    /// A plain count or offset
+    Number(u64),
+    /// A size in bytes
+    Bytes(u64),
+    /// A size in bits
+    Bits(u64),
+    /// Seconds since the Unix epoch
+    UnixTime(u64),
+    /// A flag byte, shown in hex
+    Flags(u8),
+    Bool(bool),
+    Dimensions { width: u64, height: u64 },
+    Text(String),
+}
+
+impl FieldValue {
+    /// JSON form: numbers stay numbers (units are fixed per key), dimensions
+    /// become `{"width": .., "height": ..}`
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            FieldValue::Number(n) | FieldValue::Bytes(n) | FieldValue::Bits(n) | FieldValue::UnixTime(n) => {
+                json!(n)
+            }
+            FieldValue::Flags(flags) => json!(flags),
+            FieldValue::Bool(b) => json!(b),
+            FieldValue::Dimensions { width, height } => json!({ "width": width, "height": height }),
+            FieldValue::Text(text) => json!(text),
+        }
+    }
+}
+
+impl fmt::Display for FieldValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldValue::Number(n) => write!(f, "{}", n),
+            FieldValue::Bytes(n) => write!(f, "{} bytes", n),
+            FieldValue::Bits(n) => write!(f, "{} bits", n),
+            FieldValue::UnixTime(t) => write!(f, "{} Unix timestamp", t),
+            FieldValue::Flags(flags) => write!(f, "0x{:02x}", flags),
+            FieldValue::Bool(b) => write!(f, "{}", b),
+            FieldValue::Dimensions { width, height } => write!(f, "{} x {}", width, height),
+            FieldValue::Text(text) => f.write_str(text),
+        }
+    }
+}
+
+/// One format-specific property, such as `Dimensions: 800 x 600`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatField {
+    pub label: &'static str,
+    pub value: FieldValue,
+}
+
+impl FormatField {
+    fn new(label: &'static str, value: FieldValue) -> Self {
+        FormatField { label, value }
+    }
+
+    /// Key used in JSON output: the label in snake case (`bit_depth`)
+    pub fn key(&self) -> String {
+        self.label.to_ascii_lowercase().replace(' ', "_")
+    }
+}
+
+impl fmt::Display for FormatField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.label, self.value)
+    }
+}
+
 /// Extract format-specific metadata from file data
 ///
-/// Returns a vector of formatted metadata strings.
+/// Returns a vector of formatted metadata strings, as printed by `--meta`.
 pub fn extract_format_metadata(data: &[u8]) -> Vec<String> {
+    format_fields(data).iter().map(|field| format!("  {}", field)).collect()
+}
+
+/// Format-specific fields of the detected format, in display order
+pub fn format_fields(data: &[u8]) -> Vec<FormatField> {
     let mut metadata = Vec::new();
     let format = detect_file_format(data);
 
     match format {
-        "PNG" => {
+        "PNG" if data.len() >= 26 && &data[12..16] == b"IHDR" => {
             // PNG layout: 8-byte signature, 4-byte IHDR length, 4-byte "IHDR", then
             // width (4), height (4), bit depth (1), color type (1). We need 26 bytes
             // and the chunk at offset 12 must be "IHDR" before we trust the data.
-            if data.len() >= 26 && &data[12..16] == b"IHDR" {
-                let width = u32::from_be_bytes([data[16], data[17], data[18], data[19]]);
-                let height = u32::from_be_bytes([data[20], data[21], data[22], data[23]]);
-                let bit_depth = data[24];
-                let color_type = data[25];
-                let color_type_str = match color_type {
-                    0 => "Grayscale",
-                    2 => "RGB",
-                    3 => "Indexed",
-                    4 => "Grayscale+Alpha",
-                    6 => "RGB+Alpha",
-                    _ => "Unknown",
-                };
-                metadata.push(format!("  Dimensions: {} x {}", width, height));
-                metadata.push(format!("  Bit depth: {}", bit_depth));
-                metadata.push(format!("  Color type: {}", color_type_str));
-            }
+            let width = u32::from_be_bytes([data[16], data[17], data[18], data[19]]);
+            let height = u32::from_be_bytes([data[20], data[21], data[22], data[23]]);
+            let bit_depth = data[24];
+            let color_type = data[25];
+            let color_type_str = match color_type {
+                0 => "Grayscale",
+                2 => "RGB",
+                3 => "Indexed",
+                4 => "Grayscale+Alpha",
+                6 => "RGB+Alpha",
+                _ => "Unknown",
+            };
+            metadata.push(dimensions(u64::from(width), u64::from(height)));
+            metadata.push(FormatField::new("Bit depth", FieldValue::Number(u64::from(bit_depth))));
+            metadata.push(text("Color type", color_type_str));
         }
         "BMP" => {
             // We only parse V3 BITMAPINFOHEADER or larger (size >= 40 at offset 14).
@@ -55,14 +135,14 @@ pub fn extract_format_metadata(data: &[u8]) -> Vec<String> {
                     5 => "BI_PNG",
                     _ => "Unknown",
                 };
-                metadata.push(format!("  Dimensions: {} x {}", width.abs(), height.abs()));
-                metadata.push(format!("  Bits per pixel: {}", bits_per_pixel));
-                metadata.push(format!("  Compression: {}", compression_str));
+                metadata.push(dimensions(u64::from(width.unsigned_abs()), u64::from(height.unsigned_abs())));
+                metadata.push(FormatField::new("Bits per pixel", FieldValue::Number(u64::from(bits_per_pixel))));
+                metadata.push(text("Compression", compression_str));
                 // Height can be negative for top-down DIB
                 if height < 0 {
-                    metadata.push("  Orientation: Top-down".to_string());
+                    metadata.push(text("Orientation", "Top-down"));
                 } else {
-                    metadata.push("  Orientation: Bottom-up".to_string());
+                    metadata.push(text("Orientation", "Bottom-up"));
                 }
             }
         }
@@ -87,8 +167,8 @@ pub fn extract_format_metadata(data: &[u8]) -> Vec<String> {
                     let height = (u16::from(data[i + 5]) << 8) | u16::from(data[i + 6]);
                     let width = (u16::from(data[i + 7]) << 8) | u16::from(data[i + 8]);
                     let components = data[i + 9];
-                    metadata.push(format!("  Dimensions: {} x {}", width, height));
-                    metadata.push(format!("  Components: {}", components));
+                    metadata.push(dimensions(u64::from(width), u64::from(height)));
+                    metadata.push(FormatField::new("Components", FieldValue::Number(u64::from(components))));
                     break;
                 }
                 // Non-SOF marker: advance past its length-prefixed payload.
@@ -107,148 +187,153 @@ pub fn extract_format_metadata(data: &[u8]) -> Vec<String> {
                 i += 2 + usize::from(segment_len);
             }
         }
-        "GIF" => {
-            if data.len() >= 10 {
-                let width = u16::from_le_bytes([data[6], data[7]]);
-                let height = u16::from_le_bytes([data[8], data[9]]);
-                let packed = data[10];
-                let global_color_table = (packed & 0x80) != 0;
-                let color_resolution = ((packed >> 4) & 0x07) + 1;
-                let _sorted = (packed & 0x08) != 0;
-                let global_color_table_size = 1 << ((packed & 0x07) + 1);
-                let version = if data.starts_with(b"GIF87a") { "87a" } else { "89a" };
-                metadata.push(format!("  Version: GIF{}", version));
-                metadata.push(format!("  Dimensions: {} x {}", width, height));
-                metadata.push(format!("  Global color table: {}", global_color_table));
-                if global_color_table {
-                    metadata.push(format!("  Color table size: {}", global_color_table_size));
-                }
-                metadata.push(format!("  Color resolution: {} bits", color_resolution));
+        "GIF" if data.len() >= 10 => {
+            let width = u16::from_le_bytes([data[6], data[7]]);
+            let height = u16::from_le_bytes([data[8], data[9]]);
+            let packed = data[10];
+            let global_color_table = (packed & 0x80) != 0;
+            let color_resolution = ((packed >> 4) & 0x07) + 1;
+            let _sorted = (packed & 0x08) != 0;
+            let global_color_table_size = 1 << ((packed & 0x07) + 1);
+            let version = if data.starts_with(b"GIF87a") { "87a" } else { "89a" };
+            metadata.push(text("Version", &format!("GIF{}", version)));
+            metadata.push(dimensions(u64::from(width), u64::from(height)));
+            metadata.push(FormatField::new("Global color table", FieldValue::Bool(global_color_table)));
+            if global_color_table {
+                metadata.push(FormatField::new("Color table size", FieldValue::Number(global_color_table_size)));
             }
+            metadata.push(FormatField::new("Color resolution", FieldValue::Bits(u64::from(color_resolution))));
         }
-        "ZIP" => {
-            if data.len() >= 30 && data.starts_with(b"PK\x03\x04") {
-                let compressed_size = u32::from_le_bytes([data[18], data[19], data[20], data[21]]);
-                let uncompressed_size = u32::from_le_bytes([data[22], data[23], data[24], data[25]]);
-                let compression_method = u16::from_le_bytes([data[8], data[9]]);
-                let compression_method_str = match compression_method {
-                    0 => "Stored",
-                    8 => "Deflated",
-                    9 => "Enhanced Deflated",
-                    12 => "BZIP2",
-                    14 => "LZMA",
-                    19 => "LZ77",
-                    98 => "PPMd",
-                    _ => "Unknown",
-                };
-                metadata.push(format!("  Compression: {}", compression_method_str));
-                metadata.push(format!("  Compressed size: {} bytes", compressed_size));
-                metadata.push(format!("  Uncompressed size: {} bytes", uncompressed_size));
-                // File name length at offset 26
-                let name_len = u16::from_le_bytes([data[26], data[27]]) as usize;
-                let _extra_len = u16::from_le_bytes([data[28], data[29]]) as usize;
-                if data.len() >= 30 + name_len {
-                    let name_bytes = &data[30..30 + name_len];
-                    if let Ok(name) = String::from_utf8(name_bytes.to_vec()) {
-                        metadata.push(format!("  First file: {}", name));
-                    }
+        "ZIP" if data.len() >= 30 && data.starts_with(b"PK\x03\x04") => {
+            let compressed_size = u32::from_le_bytes([data[18], data[19], data[20], data[21]]);
+            let uncompressed_size = u32::from_le_bytes([data[22], data[23], data[24], data[25]]);
+            let compression_method = u16::from_le_bytes([data[8], data[9]]);
+            let compression_method_str = match compression_method {
+                0 => "Stored",
+                8 => "Deflated",
+                9 => "Enhanced Deflated",
+                12 => "BZIP2",
+                14 => "LZMA",
+                19 => "LZ77",
+                98 => "PPMd",
+                _ => "Unknown",
+            };
+            metadata.push(text("Compression", compression_method_str));
+            metadata.push(FormatField::new("Compressed size", FieldValue::Bytes(u64::from(compressed_size))));
+            metadata.push(FormatField::new("Uncompressed size", FieldValue::Bytes(u64::from(uncompressed_size))));
+            // File name length at offset 26
+            let name_len = u16::from_le_bytes([data[26], data[27]]) as usize;
+            let _extra_len = u16::from_le_bytes([data[28], data[29]]) as usize;
+            if data.len() >= 30 + name_len {
+                let name_bytes = &data[30..30 + name_len];
+                if let Ok(name) = String::from_utf8(name_bytes.to_vec()) {
+                    metadata.push(text("First file", &name));
                 }
             }
         }
-        "GZIP" => {
-            if data.len() >= 10 {
-                let compression_method = data[2];
-                let flags = data[3];
-                let mtime = u32::from_le_bytes([data[4], data[5], data[6], data[7]]);
-                let extra_flags = data[8];
-                let os = data[9];
-                let os_str = match os {
-                    0 => "FAT filesystem (MS-DOS, OS/2, NT/Win32)",
-                    1 => "Amiga",
-                    2 => "VMS (or OpenVMS)",
-                    3 => "Unix",
-                    4 => "VM/CMS",
-                    5 => "Atari TOS",
-                    6 => "HPFS filesystem (OS/2, NT)",
-                    7 => "Macintosh",
-                    8 => "Z-System",
-                    9 => "CP/M",
-                    10 => "TOPS-20",
-                    11 => "NTFS filesystem (NT)",
-                    12 => "QDOS",
-                    13 => "Acorn RISCOS",
-                    255 => "unknown",
-                    _ => "Other",
-                };
-                let method_str = match compression_method {
-                    8 => "Deflate",
-                    _ => "Unknown",
-                };
-                metadata.push(format!("  Compression: {}", method_str));
-                if mtime != 0 {
-                    metadata.push(format!("  Modification time: {} Unix timestamp", mtime));
-                }
-                metadata.push(format!("  OS: {}", os_str));
-                metadata.push(format!("  Flags: 0x{:02x}", flags));
-                metadata.push(format!("  Extra flags: 0x{:02x}", extra_flags));
+        "GZIP" if data.len() >= 10 => {
+            let compression_method = data[2];
+            let flags = data[3];
+            let mtime = u32::from_le_bytes([data[4], data[5], data[6], data[7]]);
+            let extra_flags = data[8];
+            let os = data[9];
+            let os_str = match os {
+                0 => "FAT filesystem (MS-DOS, OS/2, NT/Win32)",
+                1 => "Amiga",
+                2 => "VMS (or OpenVMS)",
+                3 => "Unix",
+                4 => "VM/CMS",
+                5 => "Atari TOS",
+                6 => "HPFS filesystem (OS/2, NT)",
+                7 => "Macintosh",
+                8 => "Z-System",
+                9 => "CP/M",
+                10 => "TOPS-20",
+                11 => "NTFS filesystem (NT)",
+                12 => "QDOS",
+                13 => "Acorn RISCOS",
+                255 => "unknown",
+                _ => "Other",
+            };
+            let method_str = match compression_method {
+                8 => "Deflate",
+                _ => "Unknown",
+            };
+            metadata.push(text("Compression", method_str));
+            if mtime != 0 {
+                metadata.push(FormatField::new("Modification time", FieldValue::UnixTime(u64::from(mtime))));
             }
+            metadata.push(text("OS", os_str));
+            metadata.push(FormatField::new("Flags", FieldValue::Flags(flags)));
+            metadata.push(FormatField::new("Extra flags", FieldValue::Flags(extra_flags)));
         }
-        "TIFF" => {
-            if data.len() >= 16 {
-                let is_little_endian = data.starts_with(b"II");
-                // First IFD offset at bytes 4-7
-                let ifd_offset = if is_little_endian {
-                    u32::from_le_bytes([data[4], data[5], data[6], data[7]])
-                } else {
-                    u32::from_be_bytes([data[4], data[5], data[6], data[7]])
-                };
-                metadata.push(format!("  Endianness: {}", if is_little_endian { "Little" } else { "Big" }));
-                metadata.push(format!("  IFD offset: {}", ifd_offset));
-                // Try to read first IFD for basic image info
-                // This is simplified - full TIFF parsing is complex
-            }
+        "XZ" if data.len() >= 12 => {
+            // Stream header: 6-byte magic, then 2 stream-flag bytes whose low
+            // nibble of the second byte names the integrity check.
+            let check = match data[7] & 0x0F {
+                0x00 => "None",
+                0x01 => "CRC32",
+                0x04 => "CRC64",
+                0x0A => "SHA-256",
+                _ => "Unknown",
+            };
+            metadata.push(text("Check", check));
         }
-        "TAR (USTAR)" | "TAR (GNU)" => {
-            if data.len() >= 512 {
-                // Parse TAR header
-                let name = String::from_utf8_lossy(&data[0..100]).trim_end_matches('\0').to_string();
-                let _mode = String::from_utf8_lossy(&data[100..108]).trim_end_matches('\0').to_string();
-                let size_str = String::from_utf8_lossy(&data[124..136]).trim_end_matches('\0').to_string();
-                let mtime_str = String::from_utf8_lossy(&data[136..148]).trim_end_matches('\0').to_string();
-                let typeflag = data[156];
-                let linkname = String::from_utf8_lossy(&data[157..257]).trim_end_matches('\0').to_string();
-                let type_str = match typeflag as char {
-                    '0' | '\0' => "Regular file",
-                    '1' => "Hard link",
-                    '2' => "Symbolic link",
-                    '3' => "Character device",
-                    '4' => "Block device",
-                    '5' => "Directory",
-                    '6' => "FIFO",
-                    '7' => "Contiguous file",
-                    _ => "Unknown",
-                };
-                if !name.is_empty() {
-                    metadata.push(format!("  First entry: {}", name));
-                }
-                if let Ok(size) = u64::from_str_radix(&size_str, 8) {
-                    metadata.push(format!("  Size: {} bytes", size));
-                }
-                if let Ok(mtime) = u64::from_str_radix(&mtime_str, 8) {
-                    metadata.push(format!("  Modification time: {} Unix timestamp", mtime));
-                }
-                metadata.push(format!("  Type: {}", type_str));
-                if !linkname.is_empty() {
-                    metadata.push(format!("  Link name: {}", linkname));
-                }
-            }
+        "BZIP2" => {
+            // Detection guarantees data[3] is '1'..='9': block size in 100k units
+            let block_size = u64::from(data[3] - b'0') * 100_000;
+            metadata.push(FormatField::new("Block size", FieldValue::Bytes(block_size)));
         }
-        "PDF" => {
-            if data.len() >= 8 {
-                // PDF version is in bytes 5-7 (e.g., "1.4" or "2.0")
-                let version = String::from_utf8_lossy(&data[5..8]);
-                metadata.push(format!("  Version: {}", version));
+        "TIFF" if data.len() >= 16 => {
+            let is_little_endian = data.starts_with(b"II");
+            // First IFD offset at bytes 4-7
+            let ifd_offset = if is_little_endian {
+                u32::from_le_bytes([data[4], data[5], data[6], data[7]])
+            } else {
+                u32::from_be_bytes([data[4], data[5], data[6], data[7]])
+            };
+            metadata.push(text("Endianness", if is_little_endian { "Little" } else { "Big" }));
+            metadata.push(FormatField::new("IFD offset", FieldValue::Number(u64::from(ifd_offset))));
+            // Try to read first IFD for basic image info
+            // This is simplified - full TIFF parsing is complex
+        }
+        "TAR (USTAR)" | "TAR (GNU)" if data.len() >= 512 => {
+            // Parse TAR header
+            let name = String::from_utf8_lossy(&data[0..100]).trim_end_matches('\0').to_string();
+            let _mode = String::from_utf8_lossy(&data[100..108]).trim_end_matches('\0').to_string();
+            let size_str = String::from_utf8_lossy(&data[124..136]).trim_end_matches('\0').to_string();
+            let mtime_str = String::from_utf8_lossy(&data[136..148]).trim_end_matches('\0').to_string();
+            let typeflag = data[156];
+            let linkname = String::from_utf8_lossy(&data[157..257]).trim_end_matches('\0').to_string();
+            let type_str = match typeflag as char {
+                '0' | '\0' => "Regular file",
+                '1' => "Hard link",
+                '2' => "Symbolic link",
+                '3' => "Character device",
+                '4' => "Block device",
+                '5' => "Directory",
+                '6' => "FIFO",
+                '7' => "Contiguous file",
+                _ => "Unknown",
+            };
+            if !name.is_empty() {
+                metadata.push(text("First entry", &name));
+            }
+            if let Ok(size) = u64::from_str_radix(&size_str, 8) {
+                metadata.push(FormatField::new("Size", FieldValue::Bytes(size)));
+            }
+            if let Ok(mtime) = u64::from_str_radix(&mtime_str, 8) {
+                metadata.push(FormatField::new("Modification time", FieldValue::UnixTime(mtime)));
             }
+            metadata.push(text("Type", type_str));
+            if !linkname.is_empty() {
+                metadata.push(text("Link name", &linkname));
+            }
+        }
+        "PDF" if data.len() >= 8 => {
+            // PDF version is in bytes 5-7 (e.g., "1.4" or "2.0")
+            let version = String::from_utf8_lossy(&data[5..8]);
+            metadata.push(text("Version", &version));
         }
         _ => {}
     }
@@ -256,6 +341,14 @@ pub fn extract_format_metadata(data: &[u8]) -> Vec<String> {
     metadata
 }
 
+fn text(label: &'static str, value: &str) -> FormatField {
+    FormatField::new(label, FieldValue::Text(value.to_string()))
+}
+
+fn dimensions(width: u64, height: u64) -> FormatField {
+    FormatField::new("Dimensions", FieldValue::Dimensions { width, height })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -357,6 +450,42 @@ mod tests {
         assert!(metadata.is_empty(), "should refuse to decode non-IHDR first chunk: {metadata:?}");
     }
 
+    #[test]
+    fn test_extract_xz_metadata() {
+        let xz = b"\xfd7zXZ\x00\x00\x04\xe6\xd6\xb4\x46";
+        let metadata = extract_format_metadata(xz);
+        assert_eq!(metadata, vec!["  Check: CRC64".to_string()]);
+    }
+
+    #[test]
+    fn test_extract_bzip2_metadata() {
+        let metadata = extract_format_metadata(b"BZh9\x31\x41\x59\x26\x53\x59");
+        assert_eq!(metadata, vec!["  Block size: 900000 bytes".to_string()]);
+    }
+
+    #[test]
+    fn test_format_fields_are_typed() {
+        let gzip = b"\x1f\x8b\x08\x00\x10\x00\x00\x00\x02\x03";
+        let fields = format_fields(gzip);
+        let mtime = fields.iter().find(|f| f.key() == "modification_time").unwrap();
+        assert_eq!(mtime.value, FieldValue::UnixTime(16));
+        assert_eq!(mtime.to_string(), "Modification time: 16 Unix timestamp");
+        let extra = fields.iter().find(|f| f.key() == "extra_flags").unwrap();
+        assert_eq!(extra.to_string(), "Extra flags: 0x02");
+        assert_eq!(extra.value.to_json(), json!(2));
+    }
+
+    #[test]
+    fn test_field_value_json() {
+        let dimensions = FieldValue::Dimensions { width: 800, height: 600 };
+        assert_eq!(dimensions.to_string(), "800 x 600");
+        assert_eq!(dimensions.to_json(), json!({ "width": 800, "height": 600 }));
+        assert_eq!(FieldValue::Bytes(12).to_json(), json!(12));
+        assert_eq!(FieldValue::Bool(true).to_json(), json!(true));
+        assert_eq!(FieldValue::Text("RGB".into()).to_json(), json!("RGB"));
+        assert_eq!(FormatField::new("Bits per pixel", FieldValue::Bits(24)).key(), "bits_per_pixel");
+    }
+
     #[test]
     fn test_extract_unknown_format() {
         let unknown_data = b"UNKNOWN";
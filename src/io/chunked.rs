@@ -0,0 +1,78 @@
+//! Fixed-size chunked reading for constant-memory dumps
+
+use std::io;
+use std::io::Read;
+
+/// Size of each chunk handed to the callback by [`for_each_chunk`]
+pub const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Feed `reader` to `f` in chunks of at most [`CHUNK_SIZE`] bytes
+///
+/// Stops after `limit` bytes, or at end of input when `limit` is `None`.
+/// Memory use is one chunk regardless of how much is read.
+///
+/// # Returns
+/// `io::Result<u64>` - Total number of bytes passed to `f`
+pub fn for_each_chunk<R, F>(mut reader: R, limit: Option<u64>, mut f: F) -> io::Result<u64>
+where
+    R: Read,
+    F: FnMut(&[u8]) -> io::Result<()>,
+{
+    let mut buffer = vec![0u8; CHUNK_SIZE];
+    let mut total: u64 = 0;
+    loop {
+        let want = match limit {
+            Some(limit) => (limit - total).min(CHUNK_SIZE as u64) as usize,
+            None => CHUNK_SIZE,
+        };
+        if want == 0 {
+            break;
+        }
+        let n = match reader.read(&mut buffer[..want]) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        f(&buffer[..n])?;
+        total += n as u64;
+    }
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_for_each_chunk_respects_limit() -> io::Result<()> {
+        let data = vec![7u8; CHUNK_SIZE * 2 + 10];
+        let mut seen = Vec::new();
+        let total = for_each_chunk(&data[..], Some(CHUNK_SIZE as u64 + 5), |c| {
+            seen.push(c.len());
+            Ok(())
+        })?;
+        assert_eq!(total, CHUNK_SIZE as u64 + 5);
+        assert_eq!(seen, vec![CHUNK_SIZE, 5]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_for_each_chunk_until_eof() -> io::Result<()> {
+        let data: Vec<u8> = (0..=255u8).cycle().take(CHUNK_SIZE * 3 + 1).collect();
+        let mut copy = Vec::new();
+        let total = for_each_chunk(&data[..], None, |c| {
+            copy.extend_from_slice(c);
+            Ok(())
+        })?;
+        assert_eq!(total, data.len() as u64);
+        assert_eq!(copy, data);
+        Ok(())
+    }
+
+    #[test]
+    fn test_for_each_chunk_propagates_callback_error() {
+        let result = for_each_chunk(&b"abc"[..], None, |_| Err(io::Error::other("stop")));
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,114 @@
+//! Transparent decompression of gzip, zstd, xz and bzip2 inputs
+
+use std::io;
+use std::io::Read;
+
+use crate::formats::detection::detect_file_format;
+
+/// Number of leading bytes needed to recognise every supported compressor
+pub const MAGIC_LEN: usize = 6;
+
+/// A compression format `hhead` can decode on the fly
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+    Xz,
+    Bzip2,
+}
+
+impl Compression {
+    /// Identify the compressor from the first [`MAGIC_LEN`] bytes of an input
+    pub fn detect(data: &[u8]) -> Option<Compression> {
+        match detect_file_format(data) {
+            "GZIP" => Some(Compression::Gzip),
+            "ZSTD" => Some(Compression::Zstd),
+            "XZ" => Some(Compression::Xz),
+            "BZIP2" => Some(Compression::Bzip2),
+            _ => None,
+        }
+    }
+
+    /// Format tag, as returned by [`detect_file_format`]
+    pub fn name(self) -> &'static str {
+        match self {
+            Compression::Gzip => "GZIP",
+            Compression::Zstd => "ZSTD",
+            Compression::Xz => "XZ",
+            Compression::Bzip2 => "BZIP2",
+        }
+    }
+
+    /// Wrap `reader` in a decoder that yields the decompressed payload
+    ///
+    /// Concatenated gzip members, xz streams and bzip2 streams are decoded back
+    /// to back, matching `zcat`, `xzcat` and `bzcat`.
+    pub fn decoder<'a, R: Read + 'a>(self, reader: R) -> io::Result<Box<dyn Read + 'a>> {
+        Ok(match self {
+            Compression::Gzip => Box::new(flate2::read::MultiGzDecoder::new(reader)),
+            Compression::Zstd => Box::new(
+                ruzstd::decoding::StreamingDecoder::new(reader)
+                    .map_err(|e| io::Error::other(format!("Failed to read zstd frame: {}", e)))?,
+            ),
+            Compression::Xz => Box::new(xz2::read::XzDecoder::new_multi_decoder(reader)),
+            Compression::Bzip2 => Box::new(bzip2::read::MultiBzDecoder::new(reader)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const PAYLOAD: &[u8] = b"%PDF-1.7 hello from inside the archive";
+
+    fn round_trip(compression: Compression, compressed: &[u8]) -> Vec<u8> {
+        assert_eq!(Compression::detect(compressed), Some(compression));
+        let mut out = Vec::new();
+        compression.decoder(compressed).unwrap().read_to_end(&mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn test_decompress_gzip() {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(PAYLOAD).unwrap();
+        assert_eq!(round_trip(Compression::Gzip, &encoder.finish().unwrap()), PAYLOAD);
+    }
+
+    #[test]
+    fn test_decompress_zstd() {
+        let compressed = ruzstd::encoding::compress_to_vec(PAYLOAD, ruzstd::encoding::CompressionLevel::Fastest);
+        assert_eq!(round_trip(Compression::Zstd, &compressed), PAYLOAD);
+    }
+
+    #[test]
+    fn test_decompress_xz() {
+        let mut encoder = xz2::write::XzEncoder::new(Vec::new(), 6);
+        encoder.write_all(PAYLOAD).unwrap();
+        assert_eq!(round_trip(Compression::Xz, &encoder.finish().unwrap()), PAYLOAD);
+    }
+
+    #[test]
+    fn test_decompress_bzip2() {
+        let mut encoder = bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::default());
+        encoder.write_all(PAYLOAD).unwrap();
+        assert_eq!(round_trip(Compression::Bzip2, &encoder.finish().unwrap()), PAYLOAD);
+    }
+
+    #[test]
+    fn test_detect_uncompressed() {
+        assert_eq!(Compression::detect(PAYLOAD), None);
+        assert_eq!(Compression::detect(b""), None);
+    }
+
+    #[test]
+    fn test_corrupt_input_is_an_error() {
+        let mut out = Vec::new();
+        let result = Compression::Gzip
+            .decoder(&b"\x1f\x8b\x08\x00garbage"[..])
+            .and_then(|mut d| d.read_to_end(&mut out));
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,275 @@
+//! Block and character device support: type detection, sizing and aligned reads
+
+use std::fs;
+use std::io;
+use std::io::{Read, Seek, SeekFrom};
+
+use super::chunked::CHUNK_SIZE;
+use super::file::read_full;
+
+/// Sector size assumed when the device cannot be asked
+pub const DEFAULT_SECTOR_SIZE: usize = 512;
+
+/// What kind of filesystem object an input is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileKind {
+    Regular,
+    Directory,
+    BlockDevice,
+    CharDevice,
+    Fifo,
+    Socket,
+    Other,
+}
+
+impl FileKind {
+    #[cfg(unix)]
+    pub fn of(file_type: &fs::FileType) -> FileKind {
+        use std::os::unix::fs::FileTypeExt;
+        if file_type.is_file() {
+            FileKind::Regular
+        } else if file_type.is_dir() {
+            FileKind::Directory
+        } else if file_type.is_block_device() {
+            FileKind::BlockDevice
+        } else if file_type.is_char_device() {
+            FileKind::CharDevice
+        } else if file_type.is_fifo() {
+            FileKind::Fifo
+        } else if file_type.is_socket() {
+            FileKind::Socket
+        } else {
+            FileKind::Other
+        }
+    }
+
+    #[cfg(not(unix))]
+    pub fn of(file_type: &fs::FileType) -> FileKind {
+        if file_type.is_file() {
+            FileKind::Regular
+        } else if file_type.is_dir() {
+            FileKind::Directory
+        } else {
+            FileKind::Other
+        }
+    }
+
+    /// Human-readable name, as shown by `--meta`
+    pub fn describe(self) -> &'static str {
+        match self {
+            FileKind::Regular => "regular file",
+            FileKind::Directory => "directory",
+            FileKind::BlockDevice => "block device",
+            FileKind::CharDevice => "character device",
+            FileKind::Fifo => "FIFO",
+            FileKind::Socket => "socket",
+            FileKind::Other => "other",
+        }
+    }
+}
+
+/// Size of `file` in bytes
+///
+/// `fs::Metadata::len` is 0 for block devices, so those are asked directly
+/// (the `BLKGETSIZE64` ioctl on Linux, a seek to the end elsewhere).
+pub fn input_len(file: &fs::File) -> io::Result<u64> {
+    let meta = file.metadata()?;
+    if FileKind::of(&meta.file_type()) == FileKind::BlockDevice {
+        return block_device_size(file);
+    }
+    Ok(meta.len())
+}
+
+/// Logical sector size of a block device, or `None` for anything else
+pub fn sector_size(file: &fs::File) -> io::Result<Option<usize>> {
+    if FileKind::of(&file.metadata()?.file_type()) != FileKind::BlockDevice {
+        return Ok(None);
+    }
+    Ok(Some(logical_sector_size(file).unwrap_or(DEFAULT_SECTOR_SIZE)))
+}
+
+#[cfg(target_os = "linux")]
+fn block_device_size(file: &fs::File) -> io::Result<u64> {
+    use std::os::unix::io::AsRawFd;
+    const BLKGETSIZE64: libc::Ioctl = libc::_IOR::<u64>(0x12, 114);
+    let mut size: u64 = 0;
+    // SAFETY: BLKGETSIZE64 writes a single u64 through the pointer.
+    let rc = unsafe { libc::ioctl(file.as_raw_fd(), BLKGETSIZE64, &mut size as *mut u64) };
+    if rc != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(size)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn block_device_size(file: &fs::File) -> io::Result<u64> {
+    let mut file = file;
+    let size = file.seek(SeekFrom::End(0))?;
+    file.seek(SeekFrom::Start(0))?;
+    Ok(size)
+}
+
+#[cfg(target_os = "linux")]
+fn logical_sector_size(file: &fs::File) -> io::Result<usize> {
+    use std::os::unix::io::AsRawFd;
+    let mut size: libc::c_int = 0;
+    // SAFETY: BLKSSZGET writes a single int through the pointer.
+    let rc = unsafe { libc::ioctl(file.as_raw_fd(), libc::BLKSSZGET, &mut size as *mut libc::c_int) };
+    if rc != 0 || size <= 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(size as usize)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn logical_sector_size(_file: &fs::File) -> io::Result<usize> {
+    Ok(DEFAULT_SECTOR_SIZE)
+}
+
+/// Reader that only touches the underlying device at sector-aligned offsets,
+/// in sector-multiple lengths, into a sector-aligned buffer
+///
+/// Raw and `O_DIRECT` device reads reject anything else. Bytes before the
+/// requested start offset are read as part of the first sector and dropped.
+pub struct SectorReader<R> {
+    inner: R,
+    storage: Vec<u8>,
+    align: usize,
+    window: usize,
+    pos: usize,
+    filled: usize,
+    skip: usize,
+    eof: bool,
+}
+
+impl<R: Read + Seek> SectorReader<R> {
+    /// Position `inner` so that the first byte read is the one at `start`
+    pub fn new(mut inner: R, start: u64, sector_size: usize) -> io::Result<Self> {
+        let sector = sector_size.max(1);
+        let aligned = start - start % sector as u64;
+        inner.seek(SeekFrom::Start(aligned))?;
+        let window = CHUNK_SIZE.div_ceil(sector) * sector;
+        let storage = vec![0u8; window + sector];
+        let align = if sector.is_power_of_two() { storage.as_ptr().align_offset(sector) } else { 0 };
+        Ok(SectorReader {
+            inner,
+            storage,
+            align,
+            window,
+            pos: 0,
+            filled: 0,
+            skip: (start - aligned) as usize,
+            eof: false,
+        })
+    }
+}
+
+impl<R: Read + Seek> Read for SectorReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos >= self.filled {
+            if self.eof {
+                return Ok(0);
+            }
+            let window = &mut self.storage[self.align..self.align + self.window];
+            self.filled = read_full(&mut self.inner, window)?;
+            self.eof = self.filled < self.window;
+            self.pos = self.skip.min(self.filled);
+            self.skip = 0;
+        }
+        let available = &self.storage[self.align + self.pos..self.align + self.filled];
+        let n = buf.len().min(available.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// Cursor that fails any seek or read that is not sector-aligned.
+    struct StrictDevice {
+        inner: Cursor<Vec<u8>>,
+        sector: u64,
+    }
+
+    impl Read for StrictDevice {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let aligned = self.inner.position().is_multiple_of(self.sector)
+                && (buf.len() as u64).is_multiple_of(self.sector)
+                && (buf.as_ptr() as u64).is_multiple_of(self.sector);
+            if !aligned {
+                return Err(io::Error::from(io::ErrorKind::InvalidInput));
+            }
+            self.inner.read(buf)
+        }
+    }
+
+    impl Seek for StrictDevice {
+        fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+            let at = self.inner.seek(pos)?;
+            if !at.is_multiple_of(self.sector) {
+                return Err(io::Error::from(io::ErrorKind::InvalidInput));
+            }
+            Ok(at)
+        }
+    }
+
+    fn device(len: usize) -> (Vec<u8>, StrictDevice) {
+        let data: Vec<u8> = (0..len).map(|i| (i * 7 % 251) as u8).collect();
+        let dev = StrictDevice { inner: Cursor::new(data.clone()), sector: 512 };
+        (data, dev)
+    }
+
+    #[test]
+    fn test_sector_reader_unaligned_start() -> io::Result<()> {
+        let (data, dev) = device(512 * 300);
+        let mut reader = SectorReader::new(dev, 1000, 512)?;
+        let mut out = Vec::new();
+        reader.read_to_end(&mut out)?;
+        assert_eq!(out, &data[1000..]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sector_reader_small_reads() -> io::Result<()> {
+        let (data, dev) = device(4096);
+        let mut reader = SectorReader::new(dev, 3, 512)?;
+        let mut buf = [0u8; 5];
+        reader.read_exact(&mut buf)?;
+        assert_eq!(buf, data[3..8]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sector_reader_start_past_end() -> io::Result<()> {
+        let (_, dev) = device(1024);
+        let mut reader = SectorReader::new(dev, 2048, 512)?;
+        let mut out = Vec::new();
+        reader.read_to_end(&mut out)?;
+        assert!(out.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_file_kind_and_len_of_regular_file() -> io::Result<()> {
+        let temp_file = tempfile::NamedTempFile::new()?;
+        temp_file.as_file().set_len(1234)?;
+        let file = temp_file.reopen()?;
+        assert_eq!(FileKind::of(&file.metadata()?.file_type()), FileKind::Regular);
+        assert_eq!(input_len(&file)?, 1234);
+        assert_eq!(sector_size(&file)?, None);
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_file_kind_char_device() -> io::Result<()> {
+        let meta = fs::metadata("/dev/null")?;
+        assert_eq!(FileKind::of(&meta.file_type()), FileKind::CharDevice);
+        assert_eq!(FileKind::CharDevice.describe(), "character device");
+        Ok(())
+    }
+}
@@ -2,9 +2,12 @@
 
 use std::fs;
 use std::io;
-use std::io::Read;
+use std::io::{Read, Seek, SeekFrom};
 use std::path::Path;
 
+use super::chunked::CHUNK_SIZE;
+use super::device::input_len;
+
 /// Read up to `max_bytes` from a file
 ///
 /// # Arguments
@@ -14,10 +17,88 @@ use std::path::Path;
 /// # Returns
 /// `io::Result<Vec<u8>>` - Bytes read from file (up to `max_bytes`)
 pub fn read_file(path: &Path, max_bytes: usize) -> io::Result<Vec<u8>> {
+    read_file_at(path, 0, max_bytes).map(|(_, data)| data)
+}
+
+/// Resolve a signed start offset against an input of `len` bytes
+///
+/// Non-negative offsets are absolute; negative offsets count back from the end
+/// and clamp to 0 when they reach past the start.
+pub fn resolve_offset(offset: i64, len: u64) -> u64 {
+    if offset >= 0 {
+        offset as u64
+    } else {
+        len.saturating_sub(offset.unsigned_abs())
+    }
+}
+
+/// Open a file positioned at `offset`
+///
+/// # Arguments
+/// * `path` - Path to the file
+/// * `offset` - Start offset; negative values count back from the end of the file
+///
+/// # Returns
+/// `io::Result<(fs::File, u64)>` - The seeked file and the absolute start offset
+pub fn open_at(path: &Path, offset: i64) -> io::Result<(fs::File, u64)> {
     let mut file = fs::File::open(path)?;
-    let mut buffer = vec![0; max_bytes];
-    let bytes_read = file.read(&mut buffer)?;
-    buffer.truncate(bytes_read);
+    let start = resolve_offset(offset, input_len(&file)?);
+    file.seek(SeekFrom::Start(start))?;
+    Ok((file, start))
+}
+
+/// Read up to `max_bytes` from a file, starting at `offset`
+///
+/// # Arguments
+/// * `path` - Path to the file
+/// * `offset` - Start offset; negative values count back from the end of the file
+/// * `max_bytes` - Maximum number of bytes to read
+///
+/// # Returns
+/// `io::Result<(u64, Vec<u8>)>` - The absolute start offset and the bytes read
+/// from there (empty when the offset is at or past the end of the file)
+pub fn read_file_at(path: &Path, offset: i64, max_bytes: usize) -> io::Result<(u64, Vec<u8>)> {
+    let (mut file, start) = open_at(path, offset)?;
+    let data = read_up_to(&mut file, max_bytes)?;
+    Ok((start, data))
+}
+
+/// Fill `buf` from `reader`, retrying short and interrupted reads
+///
+/// A single `read` call may legally return fewer bytes than requested (pipes,
+/// network filesystems, special files), so keep reading until `buf` is full
+/// or the reader reports end of input.
+///
+/// # Returns
+/// `io::Result<usize>` - Bytes read; less than `buf.len()` only at end of input
+pub fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+/// Read up to `max_bytes` from `reader`, stopping early only at end of input
+///
+/// The buffer grows as data arrives, so a large `max_bytes` is not allocated
+/// up front. A result shorter than `max_bytes` means EOF came first.
+pub fn read_up_to<R: Read>(reader: &mut R, max_bytes: usize) -> io::Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+    while buffer.len() < max_bytes {
+        let filled = buffer.len();
+        buffer.resize(filled + (max_bytes - filled).min(CHUNK_SIZE), 0);
+        let n = read_full(reader, &mut buffer[filled..])?;
+        if filled + n < buffer.len() {
+            buffer.truncate(filled + n);
+            break;
+        }
+    }
     Ok(buffer)
 }
 
@@ -27,6 +108,34 @@ mod tests {
     use std::io::Write;
     use tempfile::NamedTempFile;
 
+    /// Reader that returns at most `step` bytes per call and injects an
+    /// `Interrupted` error before every other read, like a slow pipe.
+    struct ShortReader {
+        data: Vec<u8>,
+        pos: usize,
+        step: usize,
+        interrupt: bool,
+    }
+
+    impl ShortReader {
+        fn new(data: &[u8], step: usize) -> Self {
+            ShortReader { data: data.to_vec(), pos: 0, step, interrupt: false }
+        }
+    }
+
+    impl Read for ShortReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.interrupt = !self.interrupt;
+            if self.interrupt {
+                return Err(io::Error::from(io::ErrorKind::Interrupted));
+            }
+            let n = buf.len().min(self.step).min(self.data.len() - self.pos);
+            buf[..n].copy_from_slice(&self.data[self.pos..self.pos + n]);
+            self.pos += n;
+            Ok(n)
+        }
+    }
+
     #[test]
     fn test_read_file_exists() -> io::Result<()> {
         let mut temp_file = NamedTempFile::new()?;
@@ -64,4 +173,107 @@ mod tests {
         let result = read_file(path, 100);
         assert!(result.is_err());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_resolve_offset() {
+        assert_eq!(resolve_offset(0, 100), 0);
+        assert_eq!(resolve_offset(10, 100), 10);
+        assert_eq!(resolve_offset(200, 100), 200);
+        assert_eq!(resolve_offset(-10, 100), 90);
+        assert_eq!(resolve_offset(-200, 100), 0);
+        assert_eq!(resolve_offset(i64::MIN, 100), 0);
+    }
+
+    #[test]
+    fn test_read_file_at_positive_offset() -> io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        temp_file.write_all(b"Hello, World!")?;
+
+        let (start, data) = read_file_at(temp_file.path(), 7, 100)?;
+        assert_eq!(start, 7);
+        assert_eq!(data, b"World!");
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_file_at_negative_offset() -> io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        temp_file.write_all(b"Hello, World!")?;
+
+        let (start, data) = read_file_at(temp_file.path(), -6, 3)?;
+        assert_eq!(start, 7);
+        assert_eq!(data, b"Wor");
+
+        let (start, data) = read_file_at(temp_file.path(), -100, 5)?;
+        assert_eq!(start, 0);
+        assert_eq!(data, b"Hello");
+        Ok(())
+    }
+
+    #[test]
+    fn test_open_at_positions_file() -> io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        temp_file.write_all(b"Hello, World!")?;
+
+        let (mut file, start) = open_at(temp_file.path(), -6)?;
+        assert_eq!(start, 7);
+        let mut rest = String::new();
+        file.read_to_string(&mut rest)?;
+        assert_eq!(rest, "World!");
+
+        // Counting back past the start clamps to it
+        let (_, start) = open_at(temp_file.path(), -1000)?;
+        assert_eq!(start, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_file_at_past_end() -> io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        temp_file.write_all(b"Hello")?;
+
+        let (start, data) = read_file_at(temp_file.path(), 100, 10)?;
+        assert_eq!(start, 100);
+        assert!(data.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_full_retries_short_reads() -> io::Result<()> {
+        let mut reader = ShortReader::new(b"Hello, World!", 3);
+        let mut buf = [0u8; 10];
+        assert_eq!(read_full(&mut reader, &mut buf)?, 10);
+        assert_eq!(&buf, b"Hello, Wor");
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_full_stops_at_eof() -> io::Result<()> {
+        let mut reader = ShortReader::new(b"Hello", 2);
+        let mut buf = [0u8; 10];
+        assert_eq!(read_full(&mut reader, &mut buf)?, 5);
+        assert_eq!(&buf[..5], b"Hello");
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_up_to_across_chunks() -> io::Result<()> {
+        let data: Vec<u8> = (0..=255u8).cycle().take(CHUNK_SIZE * 2 + 17).collect();
+
+        let mut reader = ShortReader::new(&data, 1000);
+        assert_eq!(read_up_to(&mut reader, CHUNK_SIZE + 5)?, &data[..CHUNK_SIZE + 5]);
+
+        let mut reader = ShortReader::new(&data, 4093);
+        let all = read_up_to(&mut reader, usize::MAX)?;
+        assert_eq!(all.len(), data.len(), "EOF should end the read early");
+        assert_eq!(all, data);
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_up_to_zero() -> io::Result<()> {
+        let mut reader = ShortReader::new(b"abc", 1);
+        assert!(read_up_to(&mut reader, 0)?.is_empty());
+        Ok(())
+    }
+}
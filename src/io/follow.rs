@@ -0,0 +1,126 @@
+//! Follow mode: keep reading as a file grows, like `tail -f`
+
+use std::io;
+use std::io::Read;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::chunked::CHUNK_SIZE;
+
+/// Timing for [`follow`]
+#[derive(Debug, Clone)]
+pub struct FollowOptions {
+    /// How long to sleep after hitting end of file before reading again
+    pub poll_interval: Duration,
+    /// How long the input must stay idle before [`FollowEvent::Quiet`] fires
+    pub quiet_after: Duration,
+}
+
+impl Default for FollowOptions {
+    fn default() -> Self {
+        FollowOptions {
+            poll_interval: Duration::from_millis(200),
+            quiet_after: Duration::from_millis(500),
+        }
+    }
+}
+
+/// What [`follow`] reports to its handler
+#[derive(Debug, PartialEq, Eq)]
+pub enum FollowEvent<'a> {
+    /// Newly available bytes, in input order
+    Data(&'a [u8]),
+    /// No data has arrived for `quiet_after`; sent once per idle period
+    Quiet,
+}
+
+/// Read `reader` to its current end, then keep polling for appended data
+///
+/// Reading continues from the same position, so a regular file that is being
+/// appended to yields each new byte exactly once. The handler returns
+/// `Ok(false)` to stop; otherwise this only returns on an I/O error.
+pub fn follow<R, F>(mut reader: R, options: &FollowOptions, mut handler: F) -> io::Result<()>
+where
+    R: Read,
+    F: FnMut(FollowEvent<'_>) -> io::Result<bool>,
+{
+    let mut buffer = vec![0u8; CHUNK_SIZE];
+    let mut last_data = Instant::now();
+    let mut quiet_sent = false;
+    loop {
+        let n = match reader.read(&mut buffer) {
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        if n > 0 {
+            last_data = Instant::now();
+            quiet_sent = false;
+            if !handler(FollowEvent::Data(&buffer[..n]))? {
+                return Ok(());
+            }
+            continue;
+        }
+        if !quiet_sent && last_data.elapsed() >= options.quiet_after {
+            quiet_sent = true;
+            if !handler(FollowEvent::Quiet)? {
+                return Ok(());
+            }
+        }
+        thread::sleep(options.poll_interval);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::OpenOptions;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn fast() -> FollowOptions {
+        FollowOptions {
+            poll_interval: Duration::from_millis(1),
+            quiet_after: Duration::from_millis(5),
+        }
+    }
+
+    #[test]
+    fn test_follow_picks_up_appended_data() -> io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        temp_file.write_all(b"first")?;
+        let mut appender = OpenOptions::new().append(true).open(temp_file.path())?;
+
+        let reader = std::fs::File::open(temp_file.path())?;
+        let mut seen = Vec::new();
+        let mut quiets = 0;
+        follow(reader, &fast(), |event| {
+            match event {
+                FollowEvent::Data(chunk) => seen.extend_from_slice(chunk),
+                FollowEvent::Quiet => {
+                    quiets += 1;
+                    if quiets == 1 {
+                        appender.write_all(b" second")?;
+                    }
+                }
+            }
+            Ok(quiets < 2)
+        })?;
+        assert_eq!(seen, b"first second");
+        assert_eq!(quiets, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_follow_handler_can_stop_on_data() -> io::Result<()> {
+        let mut seen = Vec::new();
+        follow(&b"abc"[..], &fast(), |event| {
+            if let FollowEvent::Data(chunk) = event {
+                seen.extend_from_slice(chunk);
+            }
+            Ok(false)
+        })?;
+        assert_eq!(seen, b"abc");
+        Ok(())
+    }
+}
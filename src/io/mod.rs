@@ -1,4 +1,18 @@
 //! File I/O operations
 
+pub mod chunked;
+pub mod decompress;
+pub mod device;
 pub mod file;
-pub use file::read_file;
\ No newline at end of file
+pub mod follow;
+pub mod range;
+pub mod source;
+pub mod stream;
+pub use chunked::for_each_chunk;
+pub use decompress::Compression;
+pub use device::{input_len, sector_size, FileKind, SectorReader};
+pub use file::{open_at, read_file, read_file_at, read_full, read_up_to};
+pub use follow::{follow, FollowEvent, FollowOptions};
+pub use range::{merge_ranges, ByteRange};
+pub use source::Source;
+pub use stream::{is_stdin, is_stream, read_stream_at, skip_bytes, StreamReader};
\ No newline at end of file
@@ -0,0 +1,168 @@
+//! Byte regions selected with `--range`
+
+use std::fmt;
+use std::ops::Range;
+
+use super::file::resolve_offset;
+use crate::utils::parsing::parse_offset;
+
+/// A region as given on the command line, before the input length is known
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteRange {
+    /// First byte; negative counts back from the end of the input
+    pub start: i64,
+    pub end: RangeEnd,
+}
+
+/// Where a [`ByteRange`] stops
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RangeEnd {
+    /// Exclusive end offset, negative from the end of the input; `None`
+    /// runs to the end
+    Offset(Option<i64>),
+    /// Number of bytes from the start
+    Len(u64),
+}
+
+impl ByteRange {
+    /// Parse `START..END`, `START..` or `START+LEN`, where each part is
+    /// decimal or `0x` hex and START / END may be negative (e.g. `-512..`)
+    pub fn from_spec(spec: &str) -> Result<Self, String> {
+        let invalid = || format!("invalid range '{}', expected START..END or START+LEN", spec);
+        let offset = |s: &str| parse_offset(s).ok_or_else(invalid);
+        if let Some((start, end)) = spec.split_once("..") {
+            let start = if start.is_empty() { 0 } else { offset(start)? };
+            let end = if end.is_empty() { None } else { Some(offset(end)?) };
+            return Ok(ByteRange { start, end: RangeEnd::Offset(end) });
+        }
+        // Skip a leading sign so `-512+16` splits at the second one
+        let split = spec.get(1..).and_then(|rest| rest.find('+')).ok_or_else(invalid)? + 1;
+        let (start, len) = (&spec[..split], &spec[split + 1..]);
+        let len = offset(len)
+            .ok()
+            .and_then(|len| u64::try_from(len).ok())
+            .filter(|&len| len > 0)
+            .ok_or_else(|| format!("invalid range length '{}', expected a positive number", len))?;
+        Ok(ByteRange { start: offset(start)?, end: RangeEnd::Len(len) })
+    }
+
+    /// Whether [`resolve`](Self::resolve) needs the real input length, i.e.
+    /// a bound counts from the end; an open end just runs to the end of input
+    pub fn needs_len(&self) -> bool {
+        match self.end {
+            RangeEnd::Offset(Some(end)) => self.start < 0 || end < 0,
+            RangeEnd::Offset(None) | RangeEnd::Len(_) => self.start < 0,
+        }
+    }
+
+    /// Absolute byte range within an input of `len` bytes, clamped to it
+    /// (empty if the region lies outside the input or ends before it starts)
+    pub fn resolve(&self, len: u64) -> Range<u64> {
+        let start = resolve_offset(self.start, len).min(len);
+        let end = match self.end {
+            RangeEnd::Offset(Some(end)) => resolve_offset(end, len).min(len),
+            RangeEnd::Offset(None) => len,
+            RangeEnd::Len(n) => start.saturating_add(n).min(len),
+        };
+        start..end.max(start)
+    }
+}
+
+impl fmt::Display for ByteRange {
+    /// The range in `--range` syntax, offsets in hex
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let offset = |offset: i64| {
+            let sign = if offset < 0 { "-" } else { "" };
+            format!("{}0x{:x}", sign, offset.unsigned_abs())
+        };
+        match self.end {
+            RangeEnd::Offset(end) => {
+                write!(f, "{}..{}", offset(self.start), end.map(offset).unwrap_or_default())
+            }
+            RangeEnd::Len(len) => write!(f, "{}+{}", offset(self.start), len),
+        }
+    }
+}
+
+/// Resolve `ranges` against an input of `len` bytes, in file order, with
+/// overlapping and touching regions merged and empty ones dropped
+///
+/// Pass `u64::MAX` for a stream of unknown length when none of the ranges
+/// [`needs_len`](ByteRange::needs_len).
+pub fn merge_ranges(ranges: &[ByteRange], len: u64) -> Vec<Range<u64>> {
+    let mut regions: Vec<Range<u64>> =
+        ranges.iter().map(|range| range.resolve(len)).filter(|region| !region.is_empty()).collect();
+    regions.sort_by_key(|region| region.start);
+    let mut merged: Vec<Range<u64>> = Vec::with_capacity(regions.len());
+    for region in regions {
+        match merged.last_mut() {
+            Some(last) if region.start <= last.end => last.end = last.end.max(region.end),
+            _ => merged.push(region),
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(spec: &str) -> ByteRange {
+        ByteRange::from_spec(spec).unwrap()
+    }
+
+    #[test]
+    fn test_from_spec() {
+        assert_eq!(range("0x10..0x20"), ByteRange { start: 16, end: RangeEnd::Offset(Some(32)) });
+        assert_eq!(range("-512.."), ByteRange { start: -512, end: RangeEnd::Offset(None) });
+        assert_eq!(range("..64"), ByteRange { start: 0, end: RangeEnd::Offset(Some(64)) });
+        assert_eq!(range("100..-4"), ByteRange { start: 100, end: RangeEnd::Offset(Some(-4)) });
+        assert_eq!(range("0x1000+256"), ByteRange { start: 0x1000, end: RangeEnd::Len(256) });
+        assert_eq!(range("-512+0x10"), ByteRange { start: -512, end: RangeEnd::Len(16) });
+    }
+
+    #[test]
+    fn test_from_spec_errors() {
+        for spec in ["", "16", "+16", "x..4", "4..y", "0+0", "0+-4", "0+", "1..2..3"] {
+            assert!(ByteRange::from_spec(spec).is_err(), "{spec}");
+        }
+    }
+
+    #[test]
+    fn test_resolve() {
+        assert_eq!(range("0x10..0x20").resolve(1000), 16..32);
+        assert_eq!(range("-100..").resolve(1000), 900..1000);
+        assert_eq!(range("-100..-50").resolve(1000), 900..950);
+        assert_eq!(range("-100+10").resolve(1000), 900..910);
+        // Clamped to the input, and empty when outside it or reversed
+        assert_eq!(range("990+100").resolve(1000), 990..1000);
+        assert_eq!(range("-2000..10").resolve(1000), 0..10);
+        assert!(range("2000+1").resolve(1000).is_empty());
+        assert!(range("50..10").resolve(1000).is_empty());
+        assert_eq!(range("10+5").resolve(u64::MAX), 10..15);
+    }
+
+    #[test]
+    fn test_needs_len() {
+        assert!(!range("0..16").needs_len());
+        assert!(!range("0x100+16").needs_len());
+        assert!(!range("16..").needs_len());
+        assert!(range("-16..").needs_len());
+        assert!(range("0..-16").needs_len());
+        assert!(range("-16+4").needs_len());
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(range("16..0x20").to_string(), "0x10..0x20");
+        assert_eq!(range("-512..").to_string(), "-0x200..");
+        assert_eq!(range("0x1000+256").to_string(), "0x1000+256");
+    }
+
+    #[test]
+    fn test_merge_ranges() {
+        let ranges = [range("-16.."), range("0+16"), range("8..24"), range("24+8"), range("100+4"), range("2000+1")];
+        assert_eq!(merge_ranges(&ranges, 1000), vec![0..32, 100..104, 984..1000]);
+        assert!(merge_ranges(&[], 1000).is_empty());
+    }
+}
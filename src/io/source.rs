@@ -0,0 +1,135 @@
+//! Random-access input backed by a memory map or an in-memory buffer
+
+use memmap2::{Mmap, MmapOptions};
+use std::fs;
+use std::io;
+use std::io::Read;
+use std::ops::Deref;
+use std::path::Path;
+
+use super::device::{input_len, FileKind};
+use super::file::resolve_offset;
+use super::stream::is_stream;
+
+/// The whole input, addressable as one byte slice
+///
+/// Regular files are memory-mapped, so seeking, tailing and scanning borrow
+/// straight from the page cache without copying. Pipes and special files
+/// cannot be mapped and fall back to a buffer filled by ordinary reads.
+pub enum Source {
+    Mapped(Mmap),
+    Buffered(Vec<u8>),
+}
+
+impl Source {
+    /// Open `path`, mapping it if it is a non-empty regular file
+    pub fn open(path: &Path) -> io::Result<Source> {
+        let file = fs::File::open(path)?;
+        if is_stream(path) {
+            return Source::from_reader(file);
+        }
+        let kind = FileKind::of(&file.metadata()?.file_type());
+        let len = input_len(&file)?;
+        if !matches!(kind, FileKind::Regular | FileKind::BlockDevice) || len == 0 {
+            // Zero-length mappings are rejected by the OS
+            return Source::from_reader(file);
+        }
+        // Block devices report a zero length to mmap, so size the map explicitly.
+        // SAFETY: the map is read-only. If another process truncates the file
+        // while it is mapped, reads past the new end fault; that is the usual
+        // trade-off of mmap-based viewers and matches `--follow` not using it.
+        let len = usize::try_from(len).map_err(io::Error::other)?;
+        let map = unsafe { MmapOptions::new().len(len).map(&file)? };
+        Ok(Source::Mapped(map))
+    }
+
+    /// Buffer everything `reader` yields
+    pub fn from_reader<R: Read>(mut reader: R) -> io::Result<Source> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        Ok(Source::Buffered(data))
+    }
+
+    /// Returns true if the input is memory-mapped
+    pub fn is_mapped(&self) -> bool {
+        matches!(self, Source::Mapped(_))
+    }
+
+    /// Borrow up to `max_bytes` (all remaining if `None`) starting at `offset`
+    ///
+    /// Negative offsets count back from the end, as with
+    /// [`read_file_at`](super::file::read_file_at).
+    ///
+    /// # Returns
+    /// `(u64, &[u8])` - The absolute start offset and the borrowed bytes
+    pub fn slice(&self, offset: i64, max_bytes: Option<u64>) -> (u64, &[u8]) {
+        let bytes: &[u8] = self;
+        let start = resolve_offset(offset, bytes.len() as u64);
+        let from = usize::try_from(start).unwrap_or(usize::MAX).min(bytes.len());
+        let available = bytes.len() - from;
+        let len = max_bytes.map_or(available, |n| usize::try_from(n).unwrap_or(usize::MAX).min(available));
+        (start, &bytes[from..from + len])
+    }
+}
+
+impl Deref for Source {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Source::Mapped(map) => map,
+            Source::Buffered(data) => data,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_source_maps_regular_file() -> io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        temp_file.write_all(b"Hello, World!")?;
+
+        let source = Source::open(temp_file.path())?;
+        assert!(source.is_mapped());
+        assert_eq!(&source[..], b"Hello, World!");
+        Ok(())
+    }
+
+    #[test]
+    fn test_source_empty_file_is_buffered() -> io::Result<()> {
+        let temp_file = NamedTempFile::new()?;
+        let source = Source::open(temp_file.path())?;
+        assert!(!source.is_mapped());
+        assert!(source.is_empty());
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_source_special_file_falls_back_to_buffered() -> io::Result<()> {
+        let source = Source::open(Path::new("/dev/null"))?;
+        assert!(!source.is_mapped());
+        assert!(source.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_source_slice() {
+        let source = Source::Buffered(b"Hello, World!".to_vec());
+        assert_eq!(source.slice(0, Some(5)), (0, &b"Hello"[..]));
+        assert_eq!(source.slice(7, None), (7, &b"World!"[..]));
+        assert_eq!(source.slice(-6, Some(3)), (7, &b"Wor"[..]));
+        assert_eq!(source.slice(-100, Some(2)), (0, &b"He"[..]));
+        assert_eq!(source.slice(100, Some(2)), (100, &b""[..]));
+    }
+
+    #[test]
+    fn test_source_nonexistent() {
+        assert!(Source::open(Path::new("/nonexistent/file")).is_err());
+    }
+}
@@ -0,0 +1,222 @@
+//! Streaming input for stdin, FIFOs and character devices
+
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::io::Read;
+use std::path::Path;
+
+use super::file::read_up_to;
+
+/// Returns true if `name` refers to standard input ("-")
+pub fn is_stdin(name: &str) -> bool {
+    name == "-"
+}
+
+/// Returns true if `path` is a FIFO, socket or character device, i.e. an input
+/// that cannot be seeked or sized and must be read front to back
+#[cfg(unix)]
+pub fn is_stream(path: &Path) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    match fs::metadata(path) {
+        Ok(meta) => {
+            let ft = meta.file_type();
+            ft.is_fifo() || ft.is_socket() || ft.is_char_device()
+        }
+        Err(_) => false,
+    }
+}
+
+#[cfg(not(unix))]
+pub fn is_stream(_path: &Path) -> bool {
+    false
+}
+
+/// A reader that can look ahead at the start of a non-seekable stream
+///
+/// Bytes returned by [`StreamReader::peek`] are kept and replayed by later
+/// `read` calls, so format detection can inspect the prefix without losing it.
+pub struct StreamReader<R> {
+    inner: R,
+    buffered: Vec<u8>,
+    pos: usize,
+}
+
+impl<R: Read> StreamReader<R> {
+    pub fn new(inner: R) -> Self {
+        StreamReader { inner, buffered: Vec::new(), pos: 0 }
+    }
+
+    /// Buffer and return up to `n` bytes from the current position without
+    /// consuming them. Returns fewer bytes only at end of stream.
+    pub fn peek(&mut self, n: usize) -> io::Result<&[u8]> {
+        let available = self.buffered.len() - self.pos;
+        if available < n {
+            let mut more = Vec::new();
+            (&mut self.inner).take((n - available) as u64).read_to_end(&mut more)?;
+            self.buffered.extend_from_slice(&more);
+        }
+        let end = (self.pos + n).min(self.buffered.len());
+        Ok(&self.buffered[self.pos..end])
+    }
+}
+
+impl<R: Read> Read for StreamReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos < self.buffered.len() {
+            let n = buf.len().min(self.buffered.len() - self.pos);
+            buf[..n].copy_from_slice(&self.buffered[self.pos..self.pos + n]);
+            self.pos += n;
+            if self.pos == self.buffered.len() {
+                self.buffered.clear();
+                self.pos = 0;
+            }
+            return Ok(n);
+        }
+        self.inner.read(buf)
+    }
+}
+
+/// Discard the next `n` bytes of a stream
+///
+/// # Returns
+/// `io::Result<u64>` - Number of bytes actually skipped (less than `n` at end of stream)
+pub fn skip_bytes<R: Read>(reader: &mut R, n: u64) -> io::Result<u64> {
+    io::copy(&mut reader.take(n), &mut io::sink())
+}
+
+/// Read up to `max_bytes` from a stream, starting at `offset`
+///
+/// Positive offsets are skipped by reading and discarding. Negative offsets
+/// count back from the end of the stream, so the whole stream is consumed while
+/// only the last `-offset` bytes are retained.
+///
+/// # Returns
+/// `io::Result<(u64, Vec<u8>)>` - The absolute start offset and the bytes read
+pub fn read_stream_at<R: Read>(mut reader: R, offset: i64, max_bytes: usize) -> io::Result<(u64, Vec<u8>)> {
+    if offset >= 0 {
+        let start = offset as u64;
+        skip_bytes(&mut reader, start)?;
+        let data = read_up_to(&mut reader, max_bytes)?;
+        return Ok((start, data));
+    }
+
+    let keep = usize::try_from(offset.unsigned_abs()).unwrap_or(usize::MAX);
+    let mut window: VecDeque<u8> = VecDeque::new();
+    let mut total: u64 = 0;
+    let mut chunk = [0u8; 8192];
+    loop {
+        let n = match reader.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        total += n as u64;
+        window.extend(&chunk[..n]);
+        if window.len() > keep {
+            window.drain(..window.len() - keep);
+        }
+    }
+    let start = total - window.len() as u64;
+    let data = window.into_iter().take(max_bytes).collect();
+    Ok((start, data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reader that hands out at most `step` bytes per call, like a pipe.
+    struct Trickle<'a> {
+        data: &'a [u8],
+        step: usize,
+    }
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = buf.len().min(self.step).min(self.data.len());
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn test_is_stdin() {
+        assert!(is_stdin("-"));
+        assert!(!is_stdin("file.bin"));
+        assert!(!is_stdin("--"));
+    }
+
+    #[test]
+    fn test_is_stream_regular_file() -> io::Result<()> {
+        let temp_file = tempfile::NamedTempFile::new()?;
+        assert!(!is_stream(temp_file.path()));
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_is_stream_char_device() {
+        assert!(is_stream(Path::new("/dev/null")));
+    }
+
+    #[test]
+    fn test_peek_then_read_replays_prefix() -> io::Result<()> {
+        let mut reader = StreamReader::new(Trickle { data: b"Hello, World!", step: 2 });
+        assert_eq!(reader.peek(5)?, b"Hello");
+        assert_eq!(reader.peek(3)?, b"Hel");
+        let mut all = Vec::new();
+        reader.read_to_end(&mut all)?;
+        assert_eq!(all, b"Hello, World!");
+        Ok(())
+    }
+
+    #[test]
+    fn test_peek_past_end() -> io::Result<()> {
+        let mut reader = StreamReader::new(&b"abc"[..]);
+        assert_eq!(reader.peek(10)?, b"abc");
+        Ok(())
+    }
+
+    #[test]
+    fn test_skip_bytes() -> io::Result<()> {
+        let mut reader = Trickle { data: b"Hello, World!", step: 2 };
+        assert_eq!(skip_bytes(&mut reader, 7)?, 7);
+        assert_eq!(skip_bytes(&mut reader, 100)?, 6);
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_stream_at_skips_forward() -> io::Result<()> {
+        let reader = Trickle { data: b"Hello, World!", step: 3 };
+        let (start, data) = read_stream_at(reader, 7, 100)?;
+        assert_eq!(start, 7);
+        assert_eq!(data, b"World!");
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_stream_at_fills_across_short_reads() -> io::Result<()> {
+        let reader = Trickle { data: b"Hello, World!", step: 1 };
+        let (start, data) = read_stream_at(reader, 0, 5)?;
+        assert_eq!(start, 0);
+        assert_eq!(data, b"Hello");
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_stream_at_negative_offset() -> io::Result<()> {
+        let data: Vec<u8> = (0..=255u8).cycle().take(20000).collect();
+        let reader = Trickle { data: &data, step: 777 };
+        let (start, tail) = read_stream_at(reader, -16, 4)?;
+        assert_eq!(start, 19984);
+        assert_eq!(tail, &data[19984..19988]);
+
+        let (start, all) = read_stream_at(&b"abc"[..], -100, 10)?;
+        assert_eq!(start, 0);
+        assert_eq!(all, b"abc");
+        Ok(())
+    }
+}
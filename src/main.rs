@@ -1,19 +1,32 @@
 use clap::Parser;
 use colored::control;
+use serde_json::json;
+use std::fs;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::ops::Range;
 use std::path::Path;
 
+use hhead::cli::args::OutputFormat;
 use hhead::cli::Args;
-use hhead::display::{display_hex, display_minimap, print_metadata};
-use hhead::io::read_file;
+use hhead::display::metadata::FORMAT_PREFIX_LEN;
+use hhead::display::{
+    display_minimap, literal, minimap_pixels, reverse_hex, write_html_head, write_html_metadata,
+    write_html_minimap, write_html_section_end, write_html_section_start, write_html_tail, write_literal,
+    write_region_header, HexOptions, HexWriter, LiteralWriter, Metadata, Style, Theme,
+};
+use hhead::io::decompress::MAGIC_LEN;
+use hhead::io::{
+    follow, for_each_chunk, input_len, is_stdin, is_stream, merge_ranges, open_at, read_file, read_stream_at,
+    sector_size, skip_bytes, ByteRange, Compression, FileKind, FollowEvent, FollowOptions, SectorReader, Source,
+    StreamReader,
+};
 use hhead::utils::parsing::parse_scale;
 
-fn main() -> std::io::Result<()> {
+fn main() {
     let args = Args::parse();
 
-    // Enable color override if requested
-    if args.color {
-        control::set_override(true);
-    }
+    // Settle the color policy once, forcing it on or off for every module
+    control::set_override(args.use_color());
 
     // Validate parameters using Args::validate method
     if let Err(err) = args.validate() {
@@ -21,23 +34,148 @@ fn main() -> std::io::Result<()> {
         std::process::exit(1);
     }
 
+    let theme = match load_theme(&args) {
+        Ok(theme) => theme,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    // Like `head`, label each input once there is more than one; a failing
+    // input is reported and skipped, and the exit status records it. Reversed
+    // dumps are raw bytes, so they are concatenated without labels,
+    // exported literals are already named after their input, and JSON
+    // output gets an `input` record instead. An HTML report gives every
+    // input a section of its own.
+    let inputs = args.inputs();
+    let html = args.format == OutputFormat::Html;
+    if html {
+        let names: Vec<&str> = inputs.iter().map(|input| display_name(input)).collect();
+        print_html(|out| write_html_head(out, &format!("hhead: {}", names.join(", ")), &theme));
+    }
+    let mut failed = false;
+    for (i, input) in inputs.iter().enumerate() {
+        if html {
+            print_html(|out| write_html_section_start(out, display_name(input)));
+        } else if inputs.len() > 1 && args.format == OutputFormat::Json {
+            println!("{}", json!({ "type": "input", "file": display_name(input) }));
+        } else if inputs.len() > 1 && !args.reverse {
+            if i > 0 {
+                println!();
+            }
+            if args.export.is_none() {
+                println!("==> {} <==", display_name(input));
+            }
+        }
+        let result = if args.reverse {
+            reverse_input(&args, &theme, input)
+        } else {
+            dump_input(&args, &theme, input)
+        };
+        if let Err(e) = result {
+            eprintln!("Error: {}: {}", display_name(input), e);
+            failed = true;
+        }
+        if html {
+            print_html(write_html_section_end);
+        }
+    }
+    if html {
+        print_html(write_html_tail);
+    }
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+/// Write part of an HTML report to stdout, ignoring a closed pipe like the
+/// hex dump does.
+fn print_html(write: impl FnOnce(&mut io::StdoutLock<'static>) -> io::Result<()>) {
+    let mut out = io::stdout().lock();
+    if let Err(e) = write(&mut out).and_then(|_| out.flush())
+        && e.kind() != io::ErrorKind::BrokenPipe
+    {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn display_name(input: &str) -> &str {
+    if is_stdin(input) { "<stdin>" } else { input }
+}
+
+/// The `--theme` selection with any `--palette` overrides applied.
+///
+/// Uncolored text output never looks at the theme, so it is only loaded
+/// when it is shown; a broken theme file then can't fail a plain dump.
+fn load_theme(args: &Args) -> io::Result<Theme> {
+    if !args.use_color() && args.format != OutputFormat::Html {
+        return Ok(Theme::default());
+    }
+    let mut theme = Theme::select(args.theme.as_deref())?;
+    if let Some(spec) = &args.palette {
+        theme.palette = theme.palette.with_overrides(spec).map_err(io::Error::other)?;
+    }
+    Ok(theme)
+}
+
+/// Write the bytes shown by the dump in `input` (`--reverse`).
+fn reverse_input(args: &Args, theme: &Theme, input: &str) -> io::Result<()> {
+    let options = hex_options(args, theme);
+    let mut out = BufWriter::new(io::stdout().lock());
+    let result = if is_stdin(input) {
+        reverse_hex(io::stdin().lock(), &mut out, &options)
+    } else {
+        reverse_hex(BufReader::new(fs::File::open(input)?), &mut out, &options)
+    };
+    match result.and_then(|_| out.flush()) {
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        other => other,
+    }
+}
+
+/// Metadata, minimap and hex dump for a single input.
+fn dump_input(args: &Args, theme: &Theme, input: &str) -> io::Result<()> {
+    // A stream can't be reread from where it ended, so there is nothing to follow
+    if args.follow && (is_stdin(input) || is_stream(Path::new(input))) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "--follow needs a regular file"));
+    }
+
+    // Standard input and FIFOs / character devices are read front to back
+    if is_stdin(input) {
+        return dump_stream(args, theme, "<stdin>", None, io::stdin().lock());
+    }
+
     // Check if file exists
-    let path = Path::new(&args.input);
+    let path = Path::new(input);
     if !path.exists() {
-        eprintln!("Error: File '{}' not found", args.input);
-        std::process::exit(1);
+        return Err(io::Error::new(io::ErrorKind::NotFound, "file not found"));
+    }
+    let kind = FileKind::of(&fs::metadata(path)?.file_type());
+    if is_stream(path) {
+        return dump_stream(args, theme, input, Some(kind), fs::File::open(path)?);
+    }
+    // Compressed files are dumped as the stream their decoder produces
+    if args.decompress && Compression::detect(&read_file(path, MAGIC_LEN)?).is_some() {
+        return dump_stream(args, theme, input, Some(kind), fs::File::open(path)?);
     }
 
     // Print metadata if requested
     if args.meta {
-        print_metadata(path)?;
+        print_meta(args, theme, &Metadata::of_file(path)?)?;
     }
 
     // Display minimap if requested
     if args.minimap {
         match parse_scale(&args.minimap_scale) {
             Some((rows, cols)) => {
-                if let Err(e) = display_minimap(path, rows, cols) {
+                let result = match args.format {
+                    OutputFormat::Html => minimap_pixels(path, rows, cols)
+                        .and_then(|pixels| write_html_minimap(&mut io::stdout().lock(), &pixels, theme)),
+                    _ => display_minimap(path, rows, cols, args.use_color().then_some(theme)),
+                };
+                if let Err(e) = result {
                     eprintln!("Warning: Minimap failed: {}", e);
                     // Continue with hex dump
                 }
@@ -51,11 +189,384 @@ fn main() -> std::io::Result<()> {
         }
     }
 
-    // Read file
-    let data = read_file(path, args.bytes)?;
+    if !args.range.is_empty() {
+        return dump_file_regions(args, theme, path);
+    }
+
+    if args.mmap {
+        let source = Source::open(path)?;
+        let (start, bytes) = source.slice(args.start_offset(), args.byte_limit());
+        return dump_bytes(args, theme, input, bytes, start, Some(source.len() as u64));
+    }
+
+    // Stream the requested range through the hex writer
+    let (file, start) = open_at(path, args.start_offset())?;
+    // Size the offset column for the whole file, so inputs over 4 GiB get
+    // 16-digit offsets even when only their first rows are shown.
+    let len = input_len(&file)?;
+    if args.follow {
+        return follow_hex(args, theme, file, start, len);
+    }
+    dump_hex(args, theme, input, file_reader(file, start)?, start, Some(len))
+}
+
+/// Reader for `file`, already positioned at `start`
+fn file_reader(file: fs::File, start: u64) -> io::Result<Box<dyn Read>> {
+    // Devices may only be read in whole, aligned sectors
+    Ok(match sector_size(&file)? {
+        Some(sector) => Box::new(SectorReader::new(file, start, sector)?),
+        None => Box::new(file),
+    })
+}
+
+/// Dump a non-seekable input, decompressing it first under `--decompress`.
+fn dump_stream<R: Read>(
+    args: &Args,
+    theme: &Theme,
+    name: &str,
+    kind: Option<FileKind>,
+    reader: R,
+) -> io::Result<()> {
+    let mut reader = StreamReader::new(reader);
+    if args.decompress
+        && let Some(compression) = Compression::detect(reader.peek(MAGIC_LEN)?)
+    {
+        return dump_stream_data(args, theme, name, kind, Some(compression), compression.decoder(reader)?);
+    }
+    dump_stream_data(args, theme, name, kind, None, reader)
+}
+
+/// Metadata and hex dump for a non-seekable input, driven off its buffered prefix.
+fn dump_stream_data<R: Read>(
+    args: &Args,
+    theme: &Theme,
+    name: &str,
+    kind: Option<FileKind>,
+    compression: Option<Compression>,
+    reader: R,
+) -> io::Result<()> {
+    let mut reader = StreamReader::new(reader);
+
+    if args.meta {
+        let prefix = reader.peek(FORMAT_PREFIX_LEN)?;
+        let metadata = Metadata::of_stream(name.to_string(), kind, compression.map(Compression::name), prefix);
+        print_meta(args, theme, &metadata)?;
+    }
+    if args.minimap {
+        eprintln!("Warning: Minimap requires a regular file, skipping for '{}'", name);
+    }
+
+    if !args.range.is_empty() {
+        return dump_stream_regions(args, theme, reader);
+    }
+
+    let offset = args.start_offset();
+    if offset < 0 {
+        // Only the last -offset bytes are retained while the stream is drained
+        let max_bytes = args.byte_limit().map_or(usize::MAX, |n| n as usize);
+        let (start, data) = read_stream_at(reader, offset, max_bytes)?;
+        return dump_bytes(args, theme, name, &data, start, Some(start + data.len() as u64));
+    }
+    let start = offset as u64;
+    skip_bytes(&mut reader, start)?;
+    dump_hex(args, theme, name, reader, start, None)
+}
+
+/// Hex dump the `--range` regions of a seekable file, opening it at each one
+/// in turn (or slicing the map under `--mmap`).
+fn dump_file_regions(args: &Args, theme: &Theme, path: &Path) -> io::Result<()> {
+    if args.mmap {
+        return dump_source_regions(args, theme, &Source::open(path)?);
+    }
+    let len = input_len(&fs::File::open(path)?)?;
+    warn_empty_ranges(args, len);
+    dump_regions(args, theme, merge_ranges(&args.range, len), Some(len), |region, write| {
+        let (file, start) = open_at(path, i64::try_from(region.start).map_err(io::Error::other)?)?;
+        for_each_chunk(file_reader(file, start)?, Some(region.end - region.start), write)
+    })
+}
+
+/// Hex dump the `--range` regions of a forward-only stream.
+///
+/// Regions counted from the start are read in passing, skipping the bytes
+/// between them, and an open-ended one runs to the end of the stream; one
+/// measured from the end needs the whole stream buffered.
+fn dump_stream_regions<R: Read>(args: &Args, theme: &Theme, mut reader: R) -> io::Result<()> {
+    if args.range.iter().any(ByteRange::needs_len) {
+        return dump_source_regions(args, theme, &Source::from_reader(reader)?);
+    }
+    let mut position = 0;
+    dump_regions(args, theme, merge_ranges(&args.range, u64::MAX), None, |region, write| {
+        position += skip_bytes(&mut reader, region.start - position)?;
+        let read = for_each_chunk(&mut reader, Some(region.end - region.start), write)?;
+        position += read;
+        Ok(read)
+    })
+}
+
+/// Hex dump the `--range` regions of an in-memory or mapped input.
+fn dump_source_regions(args: &Args, theme: &Theme, source: &Source) -> io::Result<()> {
+    let len = source.len() as u64;
+    warn_empty_ranges(args, len);
+    dump_regions(args, theme, merge_ranges(&args.range, len), Some(len), |region, write| {
+        let start = i64::try_from(region.start).map_err(io::Error::other)?;
+        let (_, bytes) = source.slice(start, Some(region.end - region.start));
+        write(bytes)?;
+        Ok(bytes.len() as u64)
+    })
+}
+
+/// Warn about each `--range` that selects nothing from an input of `len` bytes.
+fn warn_empty_ranges(args: &Args, len: u64) {
+    for range in args.range.iter().filter(|range| range.resolve(len).is_empty()) {
+        eprintln!("Warning: --range {} selects no bytes of the {}-byte input", range, len);
+    }
+}
+
+/// Hex dump each of `regions` under a separator line, labelling rows with
+/// absolute offsets.
+///
+/// `read` feeds one region's bytes to the writer. `end` is the length of the
+/// input, when known, so regions are already clamped to it; it also sizes
+/// the offset column. A stream's regions may end early instead, so each is
+/// read before its separator to show the bytes actually there, except an
+/// open-ended one, which is shown as it arrives with its end left open.
+/// A region that turns out to be empty is skipped with a warning.
+fn dump_regions<F>(
+    args: &Args,
+    theme: &Theme,
+    regions: Vec<Range<u64>>,
+    end: Option<u64>,
+    mut read: F,
+) -> io::Result<()>
+where
+    F: FnMut(&Range<u64>, &mut dyn FnMut(&[u8]) -> io::Result<()>) -> io::Result<u64>,
+{
+    let options = hex_options(args, theme);
+    let html = options.style == Style::Html;
+    // An open-ended stream region has no end to size the offsets by
+    let offsets_end = end.unwrap_or_else(|| {
+        let ends = regions.iter().map(|region| if region.end == u64::MAX { region.start } else { region.end });
+        ends.max().unwrap_or(0)
+    });
+    // Separator line, then a `<pre>` of its own for HTML rows
+    let header = |out: &mut BufWriter<_>, start: u64, end: Option<u64>| {
+        write_region_header(out, start, end, &options)?;
+        if html {
+            write!(out, "<pre class=\"hex\">")?;
+        }
+        io::Result::Ok(())
+    };
+    // The region's remaining rows, then the end of its `<pre>`
+    let close = |out: &mut BufWriter<_>, writer: HexWriter<Vec<u8>>| {
+        out.write_all(&writer.finish()?)?;
+        if html {
+            writeln!(out, "</pre>")?;
+        }
+        io::Result::Ok(())
+    };
+    with_output(args, |out| {
+        let mut total = 0;
+        for region in &regions {
+            // Rows go through a buffer so the separator can follow the data
+            let mut writer = HexWriter::new(Vec::new(), options.clone(), region.start);
+            writer.set_end_offset(offsets_end);
+            let streamed = end.is_some() || region.end == u64::MAX;
+            let mut started = false;
+            let read = read(region, &mut |chunk| {
+                writer.write(chunk)?;
+                if streamed {
+                    if !started {
+                        header(out, region.start, end.map(|_| region.end))?;
+                        started = true;
+                    }
+                    out.write_all(writer.get_mut())?;
+                    writer.get_mut().clear();
+                }
+                Ok(())
+            });
+            let len = match read {
+                Ok(len) => len,
+                // Keep the page well-formed; the read error is the one to report
+                Err(e) if started => {
+                    let _ = close(out, writer);
+                    return Err(e);
+                }
+                Err(e) => return Err(e),
+            };
+            if len == 0 {
+                let end = if region.end == u64::MAX { String::new() } else { format!("0x{:x}", region.end) };
+                eprintln!("Warning: --range 0x{:x}..{} selects no bytes of the input", region.start, end);
+                continue;
+            }
+            if !started {
+                header(out, region.start, Some(region.start + len))?;
+            }
+            close(out, writer)?;
+            total += len;
+        }
+        Ok(total)
+    })
+}
+
+/// Print `--meta` output as text, a JSON record or an HTML table.
+fn print_meta(args: &Args, theme: &Theme, metadata: &Metadata) -> io::Result<()> {
+    match args.format {
+        OutputFormat::Text => metadata.print(args.use_color().then_some(theme)),
+        OutputFormat::Json => println!("{}", metadata.to_json()),
+        OutputFormat::Html => write_html_metadata(&mut io::stdout().lock(), metadata)?,
+    }
+    Ok(())
+}
+
+/// Row layout shared by every hex dump.
+fn hex_options(args: &Args, theme: &Theme) -> HexOptions {
+    HexOptions {
+        color: args.use_color(),
+        encoding: args.encoding(),
+        squeeze: !args.no_squeeze,
+        radix: args.radix,
+        word_size: args.word_size,
+        endian: args.endian,
+        typed: args.as_type,
+        typed_only: args.as_only,
+        theme: *theme,
+        highlights: args.highlight.clone(),
+        style: args.style(),
+        ..HexOptions::new(args.width())
+    }
+}
+
+/// Hex dump `file` from `start`, then keep emitting rows as data is appended.
+///
+/// A partial last row is held back until it fills or the input goes quiet.
+fn follow_hex(args: &Args, theme: &Theme, file: fs::File, start: u64, len: u64) -> io::Result<()> {
+    let options = hex_options(args, theme);
+    let stdout = io::stdout();
+    let mut writer = HexWriter::new(BufWriter::new(stdout.lock()), options, start);
+    writer.set_end_offset(len);
+    let result = follow(file, &FollowOptions::default(), |event| {
+        match event {
+            FollowEvent::Data(chunk) => writer.write(chunk)?,
+            FollowEvent::Quiet => writer.flush_pending()?,
+        }
+        writer.flush()?;
+        Ok(true)
+    });
+    match result {
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        other => other,
+    }
+}
+
+/// Hex dump `reader` chunk by chunk, labelling the first row `start`.
+///
+/// `end`, when known, fixes the offset column width up front.
+fn dump_hex<R: Read>(
+    args: &Args,
+    theme: &Theme,
+    name: &str,
+    reader: R,
+    start: u64,
+    end: Option<u64>,
+) -> io::Result<()> {
+    with_output(args, |out| {
+        write_range(args, theme, name, start, end, out, |write| for_each_chunk(reader, args.byte_limit(), write))
+    })
+}
 
-    // Display hex and characters
-    display_hex(&data, args.width, args.color, args.utf8);
+/// Hex dump an in-memory or mapped range straight from the borrowed slice.
+fn dump_bytes(
+    args: &Args,
+    theme: &Theme,
+    name: &str,
+    bytes: &[u8],
+    start: u64,
+    end: Option<u64>,
+) -> io::Result<()> {
+    with_output(args, |out| {
+        write_range(args, theme, name, start, end, out, |write| {
+            write(bytes)?;
+            Ok(bytes.len() as u64)
+        })
+    })
+}
 
+/// Run `feed` against a hex writer over `out`, then finish the dump.
+///
+/// Under `--export` the range is written as a source literal named after
+/// `name` instead. Rust's array type needs the length up front, so without
+/// an `end` to work it out from, the range is collected first. Returns how
+/// many bytes `feed` wrote.
+fn write_range<W, F>(
+    args: &Args,
+    theme: &Theme,
+    name: &str,
+    start: u64,
+    end: Option<u64>,
+    mut out: W,
+    feed: F,
+) -> io::Result<u64>
+where
+    W: Write,
+    F: FnOnce(&mut dyn FnMut(&[u8]) -> io::Result<()>) -> io::Result<u64>,
+{
+    match args.export {
+        Some(language) => {
+            let ident = literal::identifier(name, language);
+            match end {
+                Some(end) => {
+                    let len = end.saturating_sub(start).min(args.byte_limit().unwrap_or(u64::MAX));
+                    let mut writer = LiteralWriter::new(out, language, &ident, args.width(), len)?;
+                    let total = feed(&mut |chunk| writer.write(chunk))?;
+                    writer.finish().map(|_| total)
+                }
+                None => {
+                    let mut data = Vec::new();
+                    let total = feed(&mut |chunk| {
+                        data.extend_from_slice(chunk);
+                        Ok(())
+                    })?;
+                    write_literal(&mut out, &data, language, &ident, args.width()).map(|_| total)
+                }
+            }
+        }
+        None => {
+            let options = hex_options(args, theme);
+            // HTML rows go in a `<pre>` of their own
+            let html = options.style == Style::Html;
+            let opened = if html { write!(out, "<pre class=\"hex\">") } else { Ok(()) };
+            let mut writer = HexWriter::new(out, options, start);
+            if let Some(end) = end {
+                writer.set_end_offset(end);
+            }
+            let fed = opened.and_then(|_| feed(&mut |chunk| writer.write(chunk)));
+            // Close the `<pre>` even when reading failed, keeping the page well-formed
+            let closed = writer.finish().and_then(|mut out| if html { writeln!(out, "</pre>") } else { Ok(()) });
+            fed.and_then(|total| closed.map(|_| total))
+        }
+    }
+}
+
+/// Run `write` against a buffered stdout, then settle the dump.
+///
+/// `write` returns how many bytes of input it showed, which is checked
+/// against an explicit `--bytes` request.
+fn with_output<F>(args: &Args, write: F) -> io::Result<()>
+where
+    F: FnOnce(&mut BufWriter<io::StdoutLock<'static>>) -> io::Result<u64>,
+{
+    let mut out = BufWriter::new(io::stdout().lock());
+    let total = match write(&mut out).and_then(|total| out.flush().map(|_| total)) {
+        Ok(total) => total,
+        // The reader went away (e.g. `| head`); nothing useful left to do.
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
+        Err(e) => return Err(e),
+    };
+
+    // Only an explicit --bytes is a promise worth reporting on
+    if let Some(requested) = args.bytes.filter(|&n| !args.all && total < n as u64) {
+        eprintln!("Note: end of input after {} of {} requested bytes", total, requested);
+    }
     Ok(())
-}
\ No newline at end of file
+}
@@ -1,4 +1,81 @@
-//! Color conversion utilities
+//! Color conversion utilities and the color on/off policy
+
+use colored::Color;
+use std::env;
+
+/// When to emit ANSI colors (`--color=auto|always|never`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Color a terminal unless the environment says otherwise
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    /// Parse `auto`, `always` or `never`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "auto" => Some(ColorChoice::Auto),
+            "always" | "yes" | "force" => Some(ColorChoice::Always),
+            "never" | "no" | "none" => Some(ColorChoice::Never),
+            _ => None,
+        }
+    }
+
+    /// Decide whether to color output going to a terminal (or not), consulting
+    /// `NO_COLOR`, `CLICOLOR` and `CLICOLOR_FORCE` for `Auto`
+    pub fn enabled(self, is_terminal: bool) -> bool {
+        let var = |name| env::var(name).ok();
+        self.resolve(is_terminal, var("NO_COLOR"), var("CLICOLOR"), var("CLICOLOR_FORCE"))
+    }
+
+    /// [`enabled`](Self::enabled) with the environment passed in
+    ///
+    /// An explicit `always` / `never` wins; otherwise a set `CLICOLOR_FORCE`
+    /// forces color, a non-empty `NO_COLOR` or `CLICOLOR=0` disables it, and
+    /// the rest depends on whether output is a terminal.
+    fn resolve(
+        self,
+        is_terminal: bool,
+        no_color: Option<String>,
+        clicolor: Option<String>,
+        clicolor_force: Option<String>,
+    ) -> bool {
+        let set = |value: &Option<String>| value.as_deref().is_some_and(|v| !v.is_empty() && v != "0");
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto if set(&clicolor_force) => true,
+            ColorChoice::Auto if no_color.as_deref().is_some_and(|v| !v.is_empty()) => false,
+            ColorChoice::Auto if clicolor.as_deref() == Some("0") => false,
+            ColorChoice::Auto => is_terminal,
+        }
+    }
+}
+
+/// RGB value of a terminal color, using xterm's defaults for the 16 named ones
+pub fn color_to_rgb(color: Color) -> (u8, u8, u8) {
+    match color {
+        Color::Black => (0x00, 0x00, 0x00),
+        Color::Red => (0xcd, 0x00, 0x00),
+        Color::Green => (0x00, 0xcd, 0x00),
+        Color::Yellow => (0xcd, 0xcd, 0x00),
+        Color::Blue => (0x00, 0x00, 0xee),
+        Color::Magenta => (0xcd, 0x00, 0xcd),
+        Color::Cyan => (0x00, 0xcd, 0xcd),
+        Color::White => (0xe5, 0xe5, 0xe5),
+        Color::BrightBlack => (0x7f, 0x7f, 0x7f),
+        Color::BrightRed => (0xff, 0x00, 0x00),
+        Color::BrightGreen => (0x00, 0xff, 0x00),
+        Color::BrightYellow => (0xff, 0xff, 0x00),
+        Color::BrightBlue => (0x5c, 0x5c, 0xff),
+        Color::BrightMagenta => (0xff, 0x00, 0xff),
+        Color::BrightCyan => (0x00, 0xff, 0xff),
+        Color::BrightWhite => (0xff, 0xff, 0xff),
+        Color::TrueColor { r, g, b } => (r, g, b),
+    }
+}
 
 /// Convert RGB color to 256-color terminal palette index
 ///
@@ -37,6 +114,42 @@ pub fn rgb_to_256(r: u8, g: u8, b: u8) -> u8 {
 mod tests {
     use super::*;
 
+    fn env(value: &str) -> Option<String> {
+        Some(value.to_string())
+    }
+
+    #[test]
+    fn test_color_choice_explicit() {
+        assert!(ColorChoice::Always.resolve(false, env("1"), None, None));
+        assert!(!ColorChoice::Never.resolve(true, None, None, env("1")));
+    }
+
+    #[test]
+    fn test_color_choice_auto() {
+        assert!(ColorChoice::Auto.resolve(true, None, None, None));
+        assert!(!ColorChoice::Auto.resolve(false, None, None, None));
+        assert!(!ColorChoice::Auto.resolve(true, env("1"), None, None));
+        assert!(ColorChoice::Auto.resolve(true, env(""), None, None), "empty NO_COLOR is unset");
+        assert!(!ColorChoice::Auto.resolve(true, None, env("0"), None));
+        assert!(ColorChoice::Auto.resolve(true, None, env("1"), None));
+        assert!(ColorChoice::Auto.resolve(false, None, None, env("1")));
+        assert!(!ColorChoice::Auto.resolve(false, None, None, env("0")));
+    }
+
+    #[test]
+    fn test_color_choice_from_name() {
+        assert_eq!(ColorChoice::from_name("auto"), Some(ColorChoice::Auto));
+        assert_eq!(ColorChoice::from_name("ALWAYS"), Some(ColorChoice::Always));
+        assert_eq!(ColorChoice::from_name("never"), Some(ColorChoice::Never));
+        assert_eq!(ColorChoice::from_name("sometimes"), None);
+    }
+
+    #[test]
+    fn test_color_to_rgb() {
+        assert_eq!(color_to_rgb(Color::Cyan), (0x00, 0xcd, 0xcd));
+        assert_eq!(color_to_rgb(Color::TrueColor { r: 1, g: 2, b: 3 }), (1, 2, 3));
+    }
+
     #[test]
     fn test_rgb_to_256_black() {
         // Black should map to grayscale near 232
@@ -63,21 +176,21 @@ mod tests {
         // Pure red should map to RGB cube
         let result = rgb_to_256(255, 0, 0);
         // Should be in RGB cube range (16-231)
-        assert!(result >= 16 && result <= 231);
+        assert!((16..=231).contains(&result));
     }
 
     #[test]
     fn test_rgb_to_256_green() {
         // Pure green should map to RGB cube
         let result = rgb_to_256(0, 255, 0);
-        assert!(result >= 16 && result <= 231);
+        assert!((16..=231).contains(&result));
     }
 
     #[test]
     fn test_rgb_to_256_blue() {
         // Pure blue should map to RGB cube
         let result = rgb_to_256(0, 0, 255);
-        assert!(result >= 16 && result <= 231);
+        assert!((16..=231).contains(&result));
     }
 
     #[test]
@@ -3,5 +3,5 @@
 pub mod color;
 pub mod parsing;
 
-pub use color::rgb_to_256;
-pub use parsing::parse_scale;
\ No newline at end of file
+pub use color::{rgb_to_256, ColorChoice};
+pub use parsing::{parse_offset, parse_scale};
\ No newline at end of file
@@ -16,6 +16,31 @@ pub fn parse_scale(scale: &str) -> Option<(usize, usize)> {
     Some((rows, cols))
 }
 
+/// Parse a byte offset in decimal or `0x`-prefixed hex (e.g. "4096", "0x1000")
+///
+/// A leading `-` yields a negative value, which callers interpret as an offset
+/// from the end of the input. Returns `None` on malformed input or overflow.
+pub fn parse_offset(s: &str) -> Option<i64> {
+    let s = s.trim();
+    let (negative, digits) = match s.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, s.strip_prefix('+').unwrap_or(s)),
+    };
+    if digits.starts_with(['+', '-']) {
+        return None;
+    }
+    let magnitude = match digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+        Some(hex) if !hex.starts_with('+') => u64::from_str_radix(hex, 16).ok()?,
+        Some(_) => return None,
+        None => digits.parse::<u64>().ok()?,
+    };
+    if negative {
+        0i64.checked_sub_unsigned(magnitude)
+    } else {
+        i64::try_from(magnitude).ok()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -42,4 +67,28 @@ mod tests {
         assert_eq!(parse_scale("8xabc"), None);
         assert_eq!(parse_scale("abcx12"), None);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_parse_offset_valid() {
+        assert_eq!(parse_offset("0"), Some(0));
+        assert_eq!(parse_offset("4096"), Some(4096));
+        assert_eq!(parse_offset("0x1000"), Some(0x1000));
+        assert_eq!(parse_offset("0X1f"), Some(0x1f));
+        assert_eq!(parse_offset("+16"), Some(16));
+        assert_eq!(parse_offset("-16"), Some(-16));
+        assert_eq!(parse_offset("-0x10"), Some(-16));
+    }
+
+    #[test]
+    fn test_parse_offset_invalid() {
+        assert_eq!(parse_offset(""), None);
+        assert_eq!(parse_offset("-"), None);
+        assert_eq!(parse_offset("0x"), None);
+        assert_eq!(parse_offset("12abc"), None);
+        assert_eq!(parse_offset("0xzz"), None);
+        assert_eq!(parse_offset("--5"), None);
+        assert_eq!(parse_offset("-+5"), None);
+        assert_eq!(parse_offset("0x+5"), None);
+        assert_eq!(parse_offset("0x8000000000000000"), None);
+    }
+}
@@ -1,13 +1,13 @@
 //! Integration tests for hhead CLI
 
-use assert_cmd::Command;
+use assert_cmd::cargo::cargo_bin_cmd;
 use predicates::prelude::*;
 use std::io::Write;
 use tempfile::NamedTempFile;
 
 #[test]
 fn test_cli_help() {
-    let mut cmd = Command::cargo_bin("hhead").unwrap();
+    let mut cmd = cargo_bin_cmd!("hhead");
     cmd.arg("--help");
     cmd.assert()
         .success()
@@ -17,7 +17,7 @@ fn test_cli_help() {
 
 #[test]
 fn test_cli_version() {
-    let mut cmd = Command::cargo_bin("hhead").unwrap();
+    let mut cmd = cargo_bin_cmd!("hhead");
     cmd.arg("--version");
     cmd.assert()
         .success()
@@ -26,7 +26,7 @@ fn test_cli_version() {
 
 #[test]
 fn test_cli_file_not_found() {
-    let mut cmd = Command::cargo_bin("hhead").unwrap();
+    let mut cmd = cargo_bin_cmd!("hhead");
     cmd.arg("--input").arg("nonexistent.txt");
     cmd.assert()
         .failure()
@@ -39,7 +39,7 @@ fn test_cli_basic_hex_dump() -> Result<(), Box<dyn std::error::Error>> {
     let test_data = b"Hello, World!";
     temp_file.write_all(test_data)?;
 
-    let mut cmd = Command::cargo_bin("hhead").unwrap();
+    let mut cmd = cargo_bin_cmd!("hhead");
     cmd.arg("--input").arg(temp_file.path());
     cmd.assert()
         .success()
@@ -55,7 +55,7 @@ fn test_cli_with_metadata() -> Result<(), Box<dyn std::error::Error>> {
     let test_data = b"Hello";
     temp_file.write_all(test_data)?;
 
-    let mut cmd = Command::cargo_bin("hhead").unwrap();
+    let mut cmd = cargo_bin_cmd!("hhead");
     cmd.arg("--input").arg(temp_file.path()).arg("--meta");
     cmd.assert()
         .success()
@@ -71,7 +71,7 @@ fn test_cli_with_color() -> Result<(), Box<dyn std::error::Error>> {
     let test_data = b"Test";
     temp_file.write_all(test_data)?;
 
-    let mut cmd = Command::cargo_bin("hhead").unwrap();
+    let mut cmd = cargo_bin_cmd!("hhead");
     cmd.arg("--input").arg(temp_file.path()).arg("--color");
     cmd.assert().success();
     // Can't easily test color output in CI, just ensure it doesn't crash
@@ -84,7 +84,7 @@ fn test_cli_with_utf8() -> Result<(), Box<dyn std::error::Error>> {
     let test_data = "Hello, 世界!";
     temp_file.write_all(test_data.as_bytes())?;
 
-    let mut cmd = Command::cargo_bin("hhead").unwrap();
+    let mut cmd = cargo_bin_cmd!("hhead");
     cmd.arg("--input").arg(temp_file.path()).arg("--utf8");
     cmd.assert()
         .success()
@@ -96,14 +96,14 @@ fn test_cli_with_utf8() -> Result<(), Box<dyn std::error::Error>> {
 #[test]
 fn test_cli_invalid_arguments() {
     // Zero width
-    let mut cmd = Command::cargo_bin("hhead").unwrap();
+    let mut cmd = cargo_bin_cmd!("hhead");
     cmd.arg("--input").arg("test.txt").arg("--width").arg("0");
     cmd.assert()
         .failure()
         .stderr(predicate::str::contains("width must be positive"));
 
     // Zero bytes
-    let mut cmd = Command::cargo_bin("hhead").unwrap();
+    let mut cmd = cargo_bin_cmd!("hhead");
     cmd.arg("--input").arg("test.txt").arg("--bytes").arg("0");
     cmd.assert()
         .failure()
@@ -126,7 +126,7 @@ fn test_cli_minimap_invalid_scale() -> Result<(), Box<dyn std::error::Error>> {
     ];
     temp_file.write_all(&png_data)?;
 
-    let mut cmd = Command::cargo_bin("hhead").unwrap();
+    let mut cmd = cargo_bin_cmd!("hhead");
     cmd.arg("--input")
         .arg(temp_file.path())
         .arg("--minimap")
@@ -154,7 +154,7 @@ fn test_cli_png_metadata() -> Result<(), Box<dyn std::error::Error>> {
     ];
     temp_file.write_all(&png_data)?;
 
-    let mut cmd = Command::cargo_bin("hhead").unwrap();
+    let mut cmd = cargo_bin_cmd!("hhead");
     cmd.arg("--input").arg(temp_file.path()).arg("--meta");
     cmd.assert()
         .success()
@@ -162,4 +162,843 @@ fn test_cli_png_metadata() -> Result<(), Box<dyn std::error::Error>> {
         .stdout(predicate::str::contains("Dimensions"));
 
     Ok(())
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_cli_skip_offsets() -> Result<(), Box<dyn std::error::Error>> {
+    let mut temp_file = NamedTempFile::new()?;
+    let test_data: Vec<u8> = (0u8..64).collect();
+    temp_file.write_all(&test_data)?;
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.arg("--input").arg(temp_file.path())
+        .args(["--skip", "0x20", "--width", "16", "--bytes", "16"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with("00000020: 20 21 22 23"));
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.arg("--input").arg(temp_file.path())
+        .args(["--offset", "-16", "--width", "16"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with("00000030: 30 31 32 33"));
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.arg("--input").arg(temp_file.path()).args(["--skip", "0xzz"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid offset"));
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_reads_stdin() {
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.write_stdin("Hello from a pipe");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with("00000000: 48 65 6c 6c 6f"))
+        .stdout(predicate::str::contains("Hello from a pipe"));
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.args(["--input", "-", "--skip", "6", "--width", "16"]).write_stdin("Hello from a pipe");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with("00000006: 66 72 6f 6d"));
+}
+
+#[test]
+fn test_cli_stdin_metadata_skips_filesystem_fields() {
+    let png_header = [
+        0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, 0x00, 0x00, 0x00, 0x0D, 0x49, 0x48, 0x44, 0x52,
+        0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x03, 0x08, 0x06, 0x00, 0x00, 0x00,
+    ];
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.args(["--meta", "--bytes", "8"]).write_stdin(png_header.to_vec());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("File: <stdin>"))
+        .stdout(predicate::str::contains("Format: PNG"))
+        .stdout(predicate::str::contains("Dimensions: 2 x 3"))
+        .stdout(predicate::str::contains("Permissions").not())
+        .stdout(predicate::str::contains("Modified").not())
+        .stdout(predicate::str::contains("00000000: 89 50 4e 47 0d 0a 1a 0a"));
+}
+
+#[test]
+fn test_cli_all_streams_whole_file() -> Result<(), Box<dyn std::error::Error>> {
+    let mut temp_file = NamedTempFile::new()?;
+    let test_data: Vec<u8> = (0..=255u8).cycle().take(200_000).collect();
+    temp_file.write_all(&test_data)?;
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.arg("--input").arg(temp_file.path()).args(["--all", "--width", "16"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let text = String::from_utf8(output)?;
+    assert_eq!(text.lines().count(), 200_000 / 16);
+    assert!(text.lines().last().unwrap().starts_with("00030d30: 30 31 32"));
+
+    // A --bytes far larger than the file must not be allocated up front
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.arg("--input").arg(temp_file.path()).args(["--bytes", "20000000000", "--width", "16"]);
+    cmd.assert().success();
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_reports_eof_before_requested_bytes() -> Result<(), Box<dyn std::error::Error>> {
+    let mut temp_file = NamedTempFile::new()?;
+    temp_file.write_all(b"Hello, World!")?;
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.arg("--input").arg(temp_file.path()).args(["--bytes", "100"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Hello, World!"))
+        .stderr(predicate::str::contains("end of input after 13 of 100 requested bytes"));
+
+    // The implicit default limit is not worth a note
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.arg("--input").arg(temp_file.path());
+    cmd.assert().success().stderr(predicate::str::is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_tail() -> Result<(), Box<dyn std::error::Error>> {
+    let mut temp_file = NamedTempFile::new()?;
+    let test_data: Vec<u8> = (0u8..100).collect();
+    temp_file.write_all(&test_data)?;
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.arg("--input").arg(temp_file.path()).args(["--tail", "20", "--width", "16"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with("00000050: 50 51 52"))
+        .stdout(predicate::str::contains("00000060: 60 61 62 63"));
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.args(["--tail", "4", "--width", "16"]).write_stdin(test_data.clone());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with("00000060: 60 61 62 63"));
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.arg("--input").arg(temp_file.path()).args(["--tail", "4", "--skip", "8"]);
+    cmd.assert().failure();
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_tail_of_file_over_4gib_uses_16_digit_offsets() -> Result<(), Box<dyn std::error::Error>> {
+    // Sparse file: no disk blocks are allocated for the hole
+    let temp_file = NamedTempFile::new()?;
+    temp_file.as_file().set_len(0x1_0000_0010)?;
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.arg("--input").arg(temp_file.path()).args(["--tail", "16", "--width", "16"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with("0000000100000000: 00 00"));
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.arg("--input").arg(temp_file.path()).args(["--bytes", "16", "--width", "16"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with("0000000000000000: 00 00"));
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_follow() -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = NamedTempFile::new()?;
+    file.write_all(&(0u8..20).collect::<Vec<u8>>())?;
+    file.flush()?;
+
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin!("hhead"))
+        .arg(file.path())
+        .args(["--follow", "--width", "8"])
+        .stdout(std::process::Stdio::piped())
+        .spawn()?;
+    let mut stdout = child.stdout.take().expect("piped stdout");
+    let reader = std::thread::spawn(move || {
+        let mut text = String::new();
+        std::io::Read::read_to_string(&mut stdout, &mut text).map(|_| text)
+    });
+
+    // The partial last row is shown once the file goes quiet, and rows
+    // after it line up with the 8-byte grid again
+    std::thread::sleep(std::time::Duration::from_millis(1500));
+    file.write_all(&(20u8..30).collect::<Vec<u8>>())?;
+    file.flush()?;
+    std::thread::sleep(std::time::Duration::from_millis(1500));
+    child.kill()?;
+    child.wait()?;
+
+    let text = reader.join().expect("reader thread")?;
+    let offsets: Vec<&str> = text.lines().map(|line| &line[..8]).collect();
+    assert_eq!(offsets, ["00000000", "00000008", "00000010", "00000014", "00000018"], "{text}");
+    assert!(text.lines().nth(3).unwrap().starts_with("00000014: 14 15 16 17 "), "{text}");
+
+    // A stream ends for good, so there is nothing to follow
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.arg("--follow").write_stdin("abc");
+    cmd.assert().failure().stderr(predicate::str::contains("--follow needs a regular file"));
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_multiple_inputs_with_banners() -> Result<(), Box<dyn std::error::Error>> {
+    let mut first = NamedTempFile::new()?;
+    first.write_all(b"GIF89a first")?;
+    let mut second = NamedTempFile::new()?;
+    second.write_all(b"%PDF-1.7 second")?;
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.arg("--meta")
+        .arg("--input")
+        .arg(first.path())
+        .arg("missing.bin")
+        .arg(second.path());
+    let assert = cmd
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Error: missing.bin: file not found"));
+    let stdout = String::from_utf8(assert.get_output().stdout.clone())?;
+
+    let first_banner = stdout.find(&format!("==> {} <==", first.path().display())).expect(&stdout);
+    let missing_banner = stdout.find("==> missing.bin <==").expect(&stdout);
+    let second_banner = stdout.find(&format!("==> {} <==", second.path().display())).expect(&stdout);
+    assert!(first_banner < missing_banner && missing_banner < second_banner, "{stdout}");
+
+    let gif = stdout.find("Format: GIF").expect(&stdout);
+    let pdf = stdout.find("Format: PDF").expect(&stdout);
+    assert!(first_banner < gif && gif < missing_banner, "{stdout}");
+    assert!(second_banner < pdf, "{stdout}");
+    assert!(stdout.contains("|%PDF-1.7 second"), "{stdout}");
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_single_input_has_no_banner() -> Result<(), Box<dyn std::error::Error>> {
+    let mut temp_file = NamedTempFile::new()?;
+    temp_file.write_all(b"Hello")?;
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.arg(temp_file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with("00000000:"));
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_mmap_matches_buffered_output() -> Result<(), Box<dyn std::error::Error>> {
+    let mut temp_file = NamedTempFile::new()?;
+    let test_data: Vec<u8> = (0..=255u8).cycle().take(5000).collect();
+    temp_file.write_all(&test_data)?;
+
+    for extra in [
+        &["--bytes", "300"][..],
+        &["--skip", "0x100", "--bytes", "64"][..],
+        &["--tail", "40"][..],
+        &["--all"][..],
+    ] {
+        let mut plain = cargo_bin_cmd!("hhead");
+        plain.arg("--input").arg(temp_file.path()).args(["--width", "16"]).args(extra);
+        let expected = plain.assert().success().get_output().stdout.clone();
+
+        let mut mapped = cargo_bin_cmd!("hhead");
+        mapped.arg("--input").arg(temp_file.path()).args(["--width", "16", "--mmap"]).args(extra);
+        mapped.assert().success().stdout(expected);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_decompress_gzip() -> Result<(), Box<dyn std::error::Error>> {
+    use flate2::{write::GzEncoder, Compression};
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(b"%PDF-1.7 payload inside gzip")?;
+    let compressed = encoder.finish()?;
+    let mut temp_file = NamedTempFile::new()?;
+    temp_file.write_all(&compressed)?;
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.arg("--input").arg(temp_file.path()).args(["--decompress", "--meta", "--skip", "4"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Compression: GZIP (showing decompressed data)"))
+        .stdout(predicate::str::contains("Format: PDF"))
+        .stdout(predicate::str::contains("00000004: 2d 31 2e 37"));
+
+    // Piped input is decompressed too
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.args(["--decompress", "--tail", "6"]).write_stdin(compressed);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("00000016: 65 20 67 7a 69 70"));
+
+    // Without the flag the compressed bytes are shown as-is
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.arg("--input").arg(temp_file.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with("00000000: 1f 8b"));
+
+    // Uncompressed input passes through unchanged
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.arg("--decompress").write_stdin("plain text");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("|plain text"));
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_squeeze_repeated_rows() -> Result<(), Box<dyn std::error::Error>> {
+    let mut data = vec![0u8; 16 * 8];
+    data.extend_from_slice(b"tail");
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.args(["--width", "16", "--all"]).write_stdin(data.clone());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::is_match(r"^00000000: 00[^\n]*\n\*\n00000080: 74 61 69 6c ")?);
+
+    // -v shows every row
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.args(["--width", "16", "--all", "-v"]).write_stdin(data);
+    let output = cmd.output()?;
+    let stdout = String::from_utf8(output.stdout)?;
+    assert_eq!(stdout.lines().count(), 9);
+    assert!(!stdout.contains('*'));
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_radix() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.args(["--radix", "bin", "--width", "8"]).write_stdin(vec![0x81, b'A']);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with("00000000: 10000001 01000001 "))
+        .stdout(predicate::str::contains("|.A"));
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.args(["--radix", "base64"]).write_stdin("x");
+    cmd.assert().failure().stderr(predicate::str::contains("invalid radix"));
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_word_size_and_endian() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.args(["--width", "8", "--word-size", "2", "--endian", "be"]).write_stdin("ABCDEFGH");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with("00000000: 4142 4344 4546 4748  |ABCDEFGH"));
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.args(["--width", "6", "--word-size", "4"]).write_stdin("x");
+    cmd.assert().failure().stderr(predicate::str::contains("multiple of the word size"));
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.args(["--word-size", "3"]).write_stdin("x");
+    cmd.assert().failure().stderr(predicate::str::contains("invalid word size"));
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_typed_column() -> Result<(), Box<dyn std::error::Error>> {
+    let data: Vec<u8> = [-1i32, 70000].iter().flat_map(|v| v.to_be_bytes()).collect();
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.args(["--width", "8", "--as", "i32", "--endian", "be"]).write_stdin(data.clone());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("ff ff ff ff 00 01 11 70           -1       70000  |"));
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.args(["--width", "8", "--as", "u32", "--as-only"]).write_stdin(data);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with("00000000: 4294967295 1880162560  |"));
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.args(["--width", "6", "--as", "f32"]).write_stdin("x");
+    cmd.assert().failure().stderr(predicate::str::contains("--as element size"));
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.arg("--as-only").write_stdin("x");
+    cmd.assert().failure();
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_palette() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.args(["--color", "--palette", "null=blue"]).write_stdin(vec![0u8, b'A']);
+    cmd.assert()
+        .success()
+        // Blue is SGR 34
+        .stdout(predicate::str::contains("\x1b[34m00\x1b[0m"));
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.args(["--palette", "null=chartreuse"]).write_stdin("x");
+    cmd.assert().failure().stderr(predicate::str::contains("unknown color"));
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_theme() -> Result<(), Box<dyn std::error::Error>> {
+    // Built-in light theme: blue offsets (SGR 34)
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.args(["--color", "--theme", "light"]).write_stdin("A");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with("\x1b[34m00000000\x1b[0m:"));
+
+    // A theme file under $XDG_CONFIG_HOME is picked up without --theme
+    let config = tempfile::tempdir()?;
+    std::fs::create_dir(config.path().join("hhead"))?;
+    std::fs::write(config.path().join("hhead/theme.toml"), "offset = \"green\"\nlabel = \"red\"\n")?;
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.env("XDG_CONFIG_HOME", config.path()).args(["--color", "--meta"]).write_stdin("A");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("\x1b[31mFile\x1b[0m: <stdin>"))
+        .stdout(predicate::str::contains("\x1b[32m00000000\x1b[0m:"));
+
+    // Without --color the theme changes nothing
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.env("XDG_CONFIG_HOME", config.path()).arg("--meta").write_stdin("A");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("File: <stdin>"))
+        .stdout(predicate::str::contains("\x1b[").not());
+
+    // A broken theme file is an error rather than silently ignored
+    std::fs::write(config.path().join("hhead/theme.toml"), "offset = \"mauve\"\n")?;
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.env("XDG_CONFIG_HOME", config.path()).arg("--color").write_stdin("A");
+    cmd.assert().failure().stderr(predicate::str::contains("unknown color 'mauve'"));
+
+    // ...but only once it is used: uncolored output doesn't load it
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.env("XDG_CONFIG_HOME", config.path()).args(["--color=never", "--export", "c"]).write_stdin("A");
+    cmd.assert().success().stdout(predicate::str::contains("0x41"));
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.args(["--color", "--theme", "solarized"]).write_stdin("A");
+    cmd.assert().failure().stderr(predicate::str::contains("unknown theme"));
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_color_policy() -> Result<(), Box<dyn std::error::Error>> {
+    let colored = || predicate::str::contains("\x1b[");
+
+    // auto (the default) leaves piped output plain
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.env_remove("CLICOLOR_FORCE").write_stdin("A");
+    cmd.assert().success().stdout(colored().not());
+
+    // CLICOLOR_FORCE colors even a pipe, unless --color=never
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.env("CLICOLOR_FORCE", "1").write_stdin("A");
+    cmd.assert().success().stdout(colored());
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.env("CLICOLOR_FORCE", "1").arg("--color=never").write_stdin("A");
+    cmd.assert().success().stdout(colored().not());
+
+    // An explicit --color=always beats NO_COLOR; a bare --color still means always
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.env("NO_COLOR", "1").arg("--color=always").write_stdin("A");
+    cmd.assert().success().stdout(colored());
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.env("NO_COLOR", "1").args(["--color", "-"]).write_stdin("A");
+    cmd.assert().success().stdout(colored());
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.arg("--color=sometimes").write_stdin("A");
+    cmd.assert().failure().stderr(predicate::str::contains("invalid color choice"));
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_minimap_follows_color_policy() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("gradient.png");
+    image::RgbImage::from_fn(4, 4, |x, _| image::Rgb([(x * 85) as u8; 3])).save(&path)?;
+
+    // Without color the minimap falls back to ASCII shades
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.arg(&path).args(["--minimap", "--minimap-scale", "2x4", "--color=never"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with(" -*@\n -*@\n"))
+        .stdout(predicate::str::contains("\x1b[").not());
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.arg(&path).args(["--minimap", "--minimap-scale", "2x4", "--color=always"]);
+    cmd.assert().success().stdout(predicate::str::contains("\x1b[38;5;"));
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_utf8_alignment() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.args(["--utf8", "--width", "8"]).write_stdin("Hello 世界 🎉\n");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("|Hello ·· |\n"))
+        .stdout(predicate::str::contains("|·界 ···  |\n"))
+        .stdout(predicate::str::contains("|·.       |\n"));
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_encoding() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.args(["--encoding", "ebcdic", "--width", "8"]).write_stdin(&b"\xc8\x85\x93\x93\x96\x6b\x40\xf1"[..]);
+    cmd.assert().success().stdout(predicate::str::contains("|Hello, 1 |\n"));
+
+    // Each UTF-16 character spans the cells of both its bytes
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.args(["--encoding", "utf16le", "--width", "4"]).write_stdin(&b"H\0i\0"[..]);
+    cmd.assert().success().stdout(predicate::str::contains("|H i  |\n"));
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.args(["--encoding", "utf16be", "--width", "3"]).write_stdin("abc");
+    cmd.assert().failure().stderr(predicate::str::contains("even"));
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.args(["--encoding", "klingon"]).write_stdin("abc");
+    cmd.assert().failure().stderr(predicate::str::contains("invalid encoding"));
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.args(["--encoding", "latin1", "--utf8"]).write_stdin("abc");
+    cmd.assert().failure();
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_highlight() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.args(["--highlight", "0x1c:4", "--highlight", "2:1:red", "--width", "16", "--color=never"])
+        .write_stdin(&[0u8; 40][..]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("00000000: 00 00[00]00 "))
+        .stdout(predicate::str::contains("00000010: 00 00 00 00 00 00 00 00  00 00 00 00[00 00 00 00] |"));
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.args(["--highlight", "1:1:blue", "--color=always"]).write_stdin("abc");
+    cmd.assert().success().stdout(predicate::str::contains("\x1b[44;30m62\x1b[0m"));
+
+    for spec in ["0x1c", "4:0", "4:4:mauve"] {
+        let mut cmd = cargo_bin_cmd!("hhead");
+        cmd.args(["--highlight", spec]).write_stdin("abc");
+        cmd.assert().failure().stderr(predicate::str::contains("--highlight"));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_xxd_style() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.args(["--style", "xxd"]).write_stdin("Hello, World!\nabcdefghijklmnopqrstuvwxyz");
+    cmd.assert().success().stdout(
+        "00000000: 4865 6c6c 6f2c 2057 6f72 6c64 210a 6162  Hello, World!.ab\n\
+         00000010: 6364 6566 6768 696a 6b6c 6d6e 6f70 7172  cdefghijklmnopqr\n\
+         00000020: 7374 7576 7778 797a                      stuvwxyz\n",
+    );
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.args(["--style", "xxd", "--radix", "oct"]).write_stdin("abc");
+    cmd.assert().failure().stderr(predicate::str::contains("--style xxd"));
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_reverse_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+    let mut data: Vec<u8> = (0..=255u8).rev().collect();
+    data.extend_from_slice(&[0u8; 4096]);
+    data.extend_from_slice("trailing text | with bars\n".as_bytes());
+    let mut file = NamedTempFile::new()?;
+    file.write_all(&data)?;
+
+    // Only the layout flags need repeating; color and highlights are ignored
+    let words = ["--radix", "bin", "--word-size", "2", "--endian", "be"];
+    let layouts: [(&[&str], &[&str]); 5] = [
+        (&[], &[]),
+        (&["--color=always", "--highlight", "0x10:64", "--width", "24"], &[]),
+        (&words, &words),
+        (&["--width", "16", "--as", "u32"], &["--as", "u32"]),
+        (&["--style", "xxd"], &["--style", "xxd"]),
+    ];
+    for (dump_flags, reverse_flags) in layouts {
+        let mut cmd = cargo_bin_cmd!("hhead");
+        cmd.arg(file.path()).arg("--all").args(dump_flags);
+        let dump = cmd.assert().success().get_output().stdout.clone();
+
+        let mut cmd = cargo_bin_cmd!("hhead");
+        cmd.arg("--reverse").args(reverse_flags).write_stdin(dump);
+        cmd.assert().success().stdout(data.clone());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_reverse_errors() {
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.arg("-r").write_stdin("00000000: 61 62  |ab|\n00000010: 63  |c|\n");
+    cmd.assert().failure().stderr(predicate::str::contains("line 2: missing bytes before offset 10"));
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.args(["-r", "--minimap"]).write_stdin("");
+    cmd.assert().failure();
+
+    // The bytes themselves aren't in an --as-only dump
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.args(["-r", "--as", "u8", "--as-only"]).write_stdin("");
+    cmd.assert().failure().stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_cli_export() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("boot-sector.bin");
+    std::fs::write(&path, b"\xeb\x3c\x90MSDOS5.0")?;
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.arg(&path).args(["--export", "c", "--width", "8"]);
+    cmd.assert().success().stdout(
+        "unsigned char boot_sector_bin[] = {\n\
+         \x20 0xeb, 0x3c, 0x90, 0x4d, 0x53, 0x44, 0x4f, 0x53,\n\
+         \x20 0x35, 0x2e, 0x30\n\
+         };\n\
+         unsigned int boot_sector_bin_len = 11;\n",
+    );
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.arg(&path).args(["--export", "rust", "--skip", "3", "--bytes", "2"]);
+    cmd.assert().success().stdout("const BOOT_SECTOR_BIN: [u8; 2] = [\n    0x4d, 0x53,\n];\n");
+
+    // Several inputs give one literal each, without `==>` headers
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.arg(&path).arg(&path).args(["--export", "go", "--bytes", "1"]);
+    let literal = "var bootSectorBin = []byte{\n\t0xeb,\n}\n";
+    cmd.assert().success().stdout(format!("{}\n{}", literal, literal));
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.args(["--export", "cobol"]).write_stdin("abc");
+    cmd.assert().failure().stderr(predicate::str::contains("invalid language"));
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_format_json() -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = NamedTempFile::new()?;
+    file.write_all(b"GIF89a\x02\x00\x01\x00\x80\x00\x00")?;
+    let name = file.path().display().to_string();
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.arg(file.path()).args(["--format", "json", "--meta", "--width", "8", "--color=always"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let records: Vec<serde_json::Value> =
+        String::from_utf8(output)?.lines().map(serde_json::from_str).collect::<Result<_, _>>()?;
+    assert_eq!(records.len(), 3);
+    assert_eq!(records[0]["type"], "metadata");
+    assert_eq!(records[0]["file"], name.as_str());
+    assert_eq!(records[0]["size"], 13);
+    assert_eq!(records[0]["format"], "GIF");
+    assert_eq!(records[0]["fields"]["dimensions"], serde_json::json!({ "width": 2, "height": 1 }));
+    assert_eq!(records[0]["fields"]["global_color_table"], true);
+    assert_eq!(
+        records[1],
+        serde_json::json!({
+            "type": "row",
+            "offset": 0,
+            "bytes": [71, 73, 70, 56, 57, 97, 2, 0],
+            "text": "GIF89a..",
+            "values": null,
+        })
+    );
+    assert_eq!(records[2]["offset"], 8);
+
+    // Several inputs are introduced by `input` records instead of headers
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.arg(file.path()).arg("-").args(["--format", "json", "--bytes", "1"]).write_stdin("x");
+    cmd.assert().success().stdout(format!(
+        "{{\"type\":\"input\",\"file\":{}}}\n\
+         {{\"type\":\"row\",\"offset\":0,\"bytes\":[71],\"text\":\"G\",\"values\":null}}\n\
+         {{\"type\":\"input\",\"file\":\"<stdin>\"}}\n\
+         {{\"type\":\"row\",\"offset\":0,\"bytes\":[120],\"text\":\"x\",\"values\":null}}\n",
+        serde_json::Value::from(name.as_str())
+    ));
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.args(["--format", "json", "--style", "xxd"]).write_stdin("abc");
+    cmd.assert().failure().stderr(predicate::str::contains("--format json cannot be combined"));
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_format_html() -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = NamedTempFile::new()?;
+    file.write_all(b"BZh9<&>\xff")?;
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.arg(file.path()).args(["--format", "html", "--meta", "--highlight", "7:1:red", "--theme", "light"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let page = String::from_utf8(output)?;
+    assert!(page.starts_with("<!DOCTYPE html>\n"));
+    assert!(page.ends_with("</body>\n</html>\n"));
+    assert!(!page.contains('\x1b'));
+    // Theme colors as classes, and the page background follows the theme
+    assert!(page.contains(".offset { color: #0000ee; }"));
+    assert!(page.contains("background: #ffffff"));
+    assert!(page.contains("<tr><th class=\"label\">Format</th><td>BZIP2</td></tr>"));
+    assert!(page.contains("<tr class=\"field\"><th class=\"label\">Block size</th><td>900000 bytes</td></tr>"));
+    // Every byte carries its offset and value, and markup in the data is escaped
+    assert!(page.contains("<span class=\"printable\" title=\"offset 0x4 (4), value 60\">3c</span>"));
+    assert!(page.contains("<span class=\"printable\" title=\"offset 0x5 (5), value 38\">&amp;</span>"));
+    assert!(page.contains(
+        "<span class=\"ff\" style=\"background: #cd0000; color: #000000\" \
+         title=\"offset 0x7 (7), value 255\">ff</span>"
+    ));
+    assert_eq!(page.matches("<section>").count(), 1);
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.args(["--format", "html", "--style", "xxd"]).write_stdin("abc");
+    cmd.assert().failure().stderr(predicate::str::contains("--format html cannot be combined"));
+
+    // A read error still closes the section's `<pre>`
+    if cfg!(target_os = "linux") {
+        let mut cmd = cargo_bin_cmd!("hhead");
+        cmd.args(["/proc/self/mem", "--format", "html", "--bytes", "16"]);
+        cmd.assert()
+            .stdout(predicate::str::contains("<pre class=\"hex\"></pre>\n</section>"))
+            .stderr(predicate::str::contains("Error: /proc/self/mem"));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_range() -> Result<(), Box<dyn std::error::Error>> {
+    let data: Vec<u8> = (0..=255u8).collect();
+    let mut file = NamedTempFile::new()?;
+    file.write_all(&data)?;
+    let expected = "\
+--- 0x0..0x6 (6 bytes) ---
+00000000: 00 01 02 03 04 05                                 |......           |
+--- 0x80..0x84 (4 bytes) ---
+00000080: 80 81 82 83                                       |....             |
+--- 0xfc..0x100 (4 bytes) ---
+000000fc: fc fd fe ff                                       |....             |
+";
+    // Overlapping header ranges merge; the trailer counts back from the end
+    let range_args = ["--width", "16", "--range", "0+4", "--range", "2..6", "--range", "0x80+4", "--range", "-4.."];
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.arg(file.path()).args(range_args);
+    cmd.assert().success().stdout(expected);
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.arg(file.path()).arg("--mmap").args(range_args);
+    cmd.assert().success().stdout(expected);
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.args(range_args).write_stdin(data.clone());
+    cmd.assert().success().stdout(expected);
+
+    // Without ranges from the end, a stream is read once in passing
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.args(["--width", "16", "--range", "0x80+2", "--range", "0+1"]).write_stdin(data.clone());
+    cmd.assert().success().stdout(
+        "--- 0x0..0x1 (1 bytes) ---\n\
+         00000000: 00                                                |.                |\n\
+         --- 0x80..0x82 (2 bytes) ---\n\
+         00000080: 80 81                                             |..               |\n",
+    );
+
+    // A stream that ends early shows the bytes it had; an open end is streamed
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.args(["--width", "16", "--range", "0xfe+8", "--range", "0x200.."]).write_stdin(data.clone());
+    cmd.assert()
+        .success()
+        .stdout(
+            "--- 0xfe..0x100 (2 bytes) ---\n\
+             000000fe: fe ff                                             |..               |\n",
+        )
+        .stderr(predicate::str::contains("--range 0x200.. selects no bytes"));
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.args(["--width", "16", "--range", "0xfc.."]).write_stdin(data);
+    cmd.assert().success().stdout(
+        "--- 0xfc.. ---\n\
+         000000fc: fc fd fe ff                                       |....             |\n",
+    );
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.arg(file.path()).args(["--range", "0x100+4", "--range", "0+1"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with("--- 0x0..0x1 (1 bytes) ---\n"))
+        .stderr(predicate::str::contains("--range 0x100+4 selects no bytes of the 256-byte input"));
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.args(["--range", "0+4", "--format", "json"]).write_stdin("AB");
+    cmd.assert().success().stdout(
+        "{\"type\":\"region\",\"start\":0,\"end\":2}\n\
+         {\"type\":\"row\",\"offset\":0,\"bytes\":[65,66],\"text\":\"AB\",\"values\":null}\n",
+    );
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.arg(file.path()).args(["--range", "0+4", "--format", "json"]);
+    cmd.assert().success().stdout(
+        "{\"type\":\"region\",\"start\":0,\"end\":4}\n\
+         {\"type\":\"row\",\"offset\":0,\"bytes\":[0,1,2,3],\"text\":\"....\",\"values\":null}\n",
+    );
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.arg(file.path()).args(["--range", "16"]);
+    cmd.assert().failure().stderr(predicate::str::contains("expected START..END or START+LEN"));
+
+    let mut cmd = cargo_bin_cmd!("hhead");
+    cmd.arg(file.path()).args(["--range", "0+4", "--skip", "4"]);
+    cmd.assert().failure().stderr(predicate::str::contains("cannot be used with"));
+
+    Ok(())
+}